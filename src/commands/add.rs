@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use google_calendar3::api::{Event, EventDateTime};
+use tokio::task::JoinSet;
+
+use super::views::render_agenda_view;
+use super::{attendee_args, get_attendees};
+use crate::util::calendar::{list_events_of_calendars, resolve_calendar_ids, Hub};
+use crate::util::config::{Config, EventDefaults};
+use crate::util::date::{
+    get_date_from_string, get_end_date, get_naive_date_from_string, parse_duration,
+    split_time_range,
+};
+use crate::util::error::{format_error, GcalError};
+use crate::util::event::{blocks_time, format_event_time, new_conference_data};
+use crate::util::plan::parse_plan;
+use crate::util::prompt;
+use crate::util::recurrence::{parse_weekdays, Frequency, Recurrence, FREQUENCIES};
+
+/// The number of occurrences shown before creating a recurring event.
+const PREVIEW_OCCURRENCES: usize = 5;
+/// The maximum number of events of a plan inserted at the same time.
+const CONCURRENT_INSERTS: usize = 10;
+
+/// Returns the options shared by the `add` subcommand and the implicit quick-add command.
+pub fn options() -> Vec<Arg> {
+    let mut options = vec![
+        Arg::new("conference")
+            .help("Indicates that this event will be a conference Google Meet")
+            .long("conference")
+            .short('c')
+            .action(ArgAction::SetTrue)
+            .required(false),
+        Arg::new("no_conference")
+            .help("Creates the event without Google Meet even if conferences are configured")
+            .long("no-conference")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("conference"),
+        Arg::new("all_day")
+            .help("Creates an all-day event, the date is then given as YYYY-MM-DD or MM-DD")
+            .long("all-day")
+            .short('a')
+            .action(ArgAction::SetTrue)
+            .required(false),
+        Arg::new("until")
+            .help("Sets the last day of a multi-day all-day event, or of the recurrence with --repeat")
+            .long("until"),
+        Arg::new("duration")
+            .help("Sets the event duration, e.g. 30m, 1h30m or 90 (minutes), defaults to 1h or the configured duration")
+            .long("duration")
+            .conflicts_with_all(["all_day", "end"]),
+        Arg::new("end")
+            .help("Sets the event end, as a time on the start day (e.g. 16:30 or 4pm) or a full date")
+            .long("end")
+            .short('e')
+            .conflicts_with("all_day"),
+        Arg::new("repeat")
+            .help("Makes the event recurring with the given frequency")
+            .long("repeat")
+            .short('r')
+            .value_parser(FREQUENCIES),
+        Arg::new("every")
+            .help("Repeats every N days, weeks, months or years")
+            .long("every")
+            .value_parser(value_parser!(u32).range(1..))
+            .requires("repeat"),
+        Arg::new("on")
+            .help("Sets the weekdays of a weekly recurrence, e.g. mon,wed")
+            .long("on")
+            .requires("repeat"),
+        Arg::new("count")
+            .help("Ends the recurrence after the given number of occurrences")
+            .long("count")
+            .value_parser(value_parser!(u32).range(1..))
+            .requires("repeat")
+            .conflicts_with("until"),
+        Arg::new("yes")
+            .help("Creates a recurring event or a plan without asking for confirmation")
+            .long("yes")
+            .short('y')
+            .action(ArgAction::SetTrue),
+        Arg::new("no_overlap")
+            .help("Refuses to create the event if it overlaps with existing events")
+            .long("no-overlap")
+            .action(ArgAction::SetTrue),
+        Arg::new("from_file")
+            .help("Creates the events of a CSV or YAML plan, or - to read the standard input")
+            .long("from-file")
+            .conflicts_with_all(["title", "date"]),
+    ];
+    options.extend(attendee_args());
+    options
+}
+
+/// Builds the `add` subcommand.
+pub fn command() -> Command {
+    Command::new("add")
+        .about("Adds a new event to Google Calendar")
+        .arg(
+            Arg::new("title")
+                .help("Sets the event title")
+                .required_unless_present("from_file"),
+        )
+        .arg(
+            Arg::new("date")
+                .help("Sets the event date, e.g. 14:00, 2024-07-27 14:00, tomorrow 10am or next fri 9:30")
+                .required_unless_present("from_file"),
+        )
+        .args(options())
+}
+
+/// Creates an event from the title and date given on the command line.
+///
+/// Without a date, the title is sent to the quick-add API which parses the date from the
+/// text itself. Otherwise a timed event, or an all-day event with `--all-day`, is created. A
+/// timed event lasts the configured duration, one hour by default, unless a range such as
+/// `10:00-10:25`, `--end` or `--duration` is given. The configured conference and reminders
+/// are added to every new event.
+/// With `--repeat`, the next occurrences are previewed before the recurring event is created.
+/// With `--from-file`, all the events of a plan are created instead, see `add_from_file`.
+/// Events overlapping with a timed event are listed as a warning, or make the command fail
+/// with `--no-overlap`.
+pub async fn run(
+    hub: &Hub,
+    tz: Tz,
+    calendar_id: &str,
+    config: &Config,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let mut defaults = config.event_defaults()?;
+    if matches.get_flag("no_conference") {
+        defaults.conference = false;
+    }
+    if let Some(path) = matches.get_one::<String>("from_file") {
+        return add_from_file(
+            hub,
+            tz,
+            calendar_id,
+            &defaults,
+            path,
+            matches.get_one::<String>("notify"),
+            matches.get_flag("yes"),
+        )
+        .await;
+    }
+    let Some(title) = matches.get_one::<String>("title") else {
+        return Ok(());
+    };
+
+    let attendees = get_attendees(matches, None)?;
+    let notify = matches.get_one::<String>("notify");
+
+    let Some(date) = matches.get_one::<String>("date") else {
+        if attendees.is_some() {
+            bail!(GcalError::Parse(
+                "Inviting attendees requires a date, e.g. gcal \"Sync\" \"tomorrow 10:00\" --invite alice@x.com"
+                    .to_string()
+            ));
+        }
+        let (_, event) = hub
+            .events()
+            .quick_add(calendar_id, title)
+            .doit()
+            .await
+            .context("Failed to create event")?;
+        println!("Event created: {}", event.html_link.unwrap_or_default());
+        return Ok(());
+    };
+
+    let repeat = matches.get_one::<String>("repeat");
+    let all_day = matches.get_flag("all_day");
+    if repeat.is_none() && !all_day && matches.contains_id("until") {
+        bail!(GcalError::Parse(
+            "--until requires --all-day or --repeat".to_string()
+        ));
+    }
+
+    let mut event = Event {
+        summary: Some(title.to_string()),
+        attendees,
+        reminders: defaults.reminders,
+        ..Default::default()
+    };
+    let first_occurrence: NaiveDateTime;
+    if all_day {
+        let first_day = get_naive_date_from_string(date)?;
+        let last_day = match matches.get_one::<String>("until") {
+            Some(until) if repeat.is_none() => get_naive_date_from_string(until)?,
+            _ => first_day,
+        };
+        if last_day < first_day {
+            bail!(GcalError::Parse(
+                "The last day of the event must not be before its first day".to_string()
+            ));
+        }
+        event.start = Some(EventDateTime {
+            date: Some(first_day),
+            ..Default::default()
+        });
+        event.end = Some(EventDateTime {
+            date: Some(last_day + Duration::days(1)),
+            ..Default::default()
+        });
+        first_occurrence = first_day.and_hms_opt(0, 0, 0).unwrap();
+    } else {
+        let (date, range_end) = split_time_range(date);
+        let event_date_with_timezone = get_date_from_string(tz, date)?;
+        let end = get_end(
+            tz,
+            event_date_with_timezone,
+            range_end,
+            defaults.duration,
+            matches,
+        )?;
+        // Recurring events are expanded in the time zone of their start, so it must be explicit.
+        let time_zone = repeat.map(|_| tz.name().to_string());
+        event.start = Some(EventDateTime {
+            date_time: Some(event_date_with_timezone),
+            time_zone: time_zone.clone(),
+            ..Default::default()
+        });
+        event.end = Some(EventDateTime {
+            date_time: Some(end),
+            time_zone,
+            ..Default::default()
+        });
+        first_occurrence = event_date_with_timezone.with_timezone(&tz).naive_local();
+        check_overlaps(
+            hub,
+            tz,
+            calendar_id,
+            (event_date_with_timezone, end),
+            matches.get_flag("no_overlap"),
+        )
+        .await?;
+    }
+    if let Some(repeat) = repeat {
+        let recurrence = get_recurrence(repeat, matches, first_occurrence)?;
+        event.recurrence = Some(vec![recurrence.to_rrule(tz, all_day)]);
+
+        println!("Next occurrences:");
+        for occurrence in recurrence.get_occurrences(first_occurrence, PREVIEW_OCCURRENCES) {
+            let format = if all_day {
+                "%a %Y-%m-%d"
+            } else {
+                "%a %Y-%m-%d %H:%M"
+            };
+            println!("  {}", occurrence.format(format));
+        }
+        if !matches.get_flag("yes") && !prompt::confirm("Create this recurring event?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+    if matches.get_flag("conference") || defaults.conference {
+        event.conference_data = Some(new_conference_data());
+    }
+
+    let mut call = hub
+        .events()
+        .insert(event, calendar_id)
+        .conference_data_version(1);
+    if let Some(notify) = notify {
+        call = call.send_updates(notify);
+    }
+    let (_, event) = call.doit().await.context("Failed to create event")?;
+    println!("Event created: {}", event.html_link.unwrap_or_default());
+    Ok(())
+}
+
+/// Creates the events of a plan, see `parse_plan` for its format.
+///
+/// The whole plan is validated and its calendars resolved before any event is created. The
+/// events are previewed and, after confirmation unless `yes` is set, inserted with one request
+/// each, `CONCURRENT_INSERTS` at a time. Events which fail are reported and do not stop the
+/// others. Attendees are notified as given by `notify`, like for a single event.
+async fn add_from_file(
+    hub: &Hub,
+    tz: Tz,
+    calendar_id: &str,
+    defaults: &EventDefaults,
+    path: &str,
+    notify: Option<&String>,
+    yes: bool,
+) -> Result<()> {
+    let input = if path == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read the standard input")?;
+        input
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?
+    };
+    let plan = parse_plan(&input, tz, defaults)?;
+
+    let mut calendar_ids: HashMap<&str, String> = HashMap::new();
+    for name in plan
+        .iter()
+        .filter_map(|planned| planned.calendar.as_deref())
+    {
+        if !calendar_ids.contains_key(name) {
+            let ids = resolve_calendar_ids(hub, &[name.to_string()]).await?;
+            calendar_ids.insert(name, ids[0].clone());
+        }
+    }
+
+    println!("Events to create:");
+    for planned in &plan {
+        println!(
+            "  {}  {}{}",
+            format_event_time(&planned.event, tz),
+            planned.event.summary.as_deref().unwrap_or_default(),
+            planned
+                .calendar
+                .as_ref()
+                .map(|calendar| format!("  ({})", calendar))
+                .unwrap_or_default()
+        );
+    }
+    if !yes && !prompt::confirm(&format!("Create {} events?", plan.len()))? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut failures = vec![];
+    for chunk in plan.chunks(CONCURRENT_INSERTS) {
+        let mut tasks = JoinSet::new();
+        for (i, planned) in chunk.iter().enumerate() {
+            let hub = hub.clone();
+            let event = planned.event.clone();
+            let calendar_id = planned
+                .calendar
+                .as_deref()
+                .map_or(calendar_id, |name| calendar_ids[name].as_str())
+                .to_string();
+            let notify = notify.cloned();
+            tasks.spawn(async move {
+                let mut call = hub
+                    .events()
+                    .insert(event, &calendar_id)
+                    .conference_data_version(1);
+                if let Some(notify) = notify {
+                    call = call.send_updates(&notify);
+                }
+                (i, call.doit().await)
+            });
+        }
+        let mut results = tasks.join_all().await;
+        results.sort_by_key(|(i, _)| *i);
+        for (i, result) in results {
+            let planned = &chunk[i];
+            let title = planned.event.summary.as_deref().unwrap_or_default();
+            match result.context("Failed to create event") {
+                Ok(_) => println!("Created: {}", title),
+                Err(e) => {
+                    eprintln!("{}: {}: {}", planned.origin, title, format_error(&e));
+                    failures.push(e);
+                }
+            }
+        }
+    }
+
+    let failed = failures.len();
+    match failures.into_iter().next() {
+        Some(e) => Err(e.context(format!(
+            "{} of {} events could not be created",
+            failed,
+            plan.len()
+        ))),
+        None => {
+            println!("Created {} events.", plan.len());
+            Ok(())
+        }
+    }
+}
+
+/// Lists the events overlapping with the time of a new event, in the same way as
+/// `list --view agenda`.
+///
+/// Only the first occurrence of a recurring event is checked. All-day events, events marked
+/// as free and declined invitations are not considered as overlapping.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+/// * `tz` - The timezone in which the overlapping events are shown.
+/// * `calendar_id` - The calendar the event is added to.
+/// * `(start, end)` - The time of the new event.
+/// * `refuse` - Whether an overlap is an error instead of a warning.
+///
+/// ## Returns
+///
+/// * `Result<()>` - A conflict error if `refuse` is set and some events overlap.
+async fn check_overlaps(
+    hub: &Hub,
+    tz: Tz,
+    calendar_id: &str,
+    (start, end): (DateTime<Utc>, DateTime<Utc>),
+    refuse: bool,
+) -> Result<()> {
+    let mut overlapping =
+        list_events_of_calendars(hub, tz, &[calendar_id.to_string()], start, end, None, None)
+            .await?;
+    overlapping.retain(|(_, event)| blocks_time(event, start, end));
+    if overlapping.is_empty() {
+        return Ok(());
+    }
+    let count = overlapping.len();
+    if refuse {
+        eprintln!("{}", render_agenda_view(tz, &overlapping));
+        bail!(GcalError::Conflict(format!(
+            "The event overlaps with {} existing event(s), remove --no-overlap to create it anyway",
+            count
+        )));
+    }
+    eprintln!(
+        "Warning: the event overlaps with {} existing event(s):",
+        count
+    );
+    eprintln!("{}", render_agenda_view(tz, &overlapping));
+    Ok(())
+}
+
+/// Computes the end of a timed event from the range in the date argument, `--end` or
+/// `--duration`, defaulting to `default_duration` after the start.
+fn get_end(
+    tz: Tz,
+    start: DateTime<Utc>,
+    range_end: Option<&str>,
+    default_duration: Duration,
+    matches: &ArgMatches,
+) -> Result<DateTime<Utc>> {
+    let end = matches.get_one::<String>("end").map(String::as_str);
+    let duration = matches.get_one::<String>("duration");
+    match (range_end, end, duration) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+            bail!(GcalError::Parse(
+                "A time range cannot be combined with --end or --duration".to_string()
+            ))
+        }
+        (Some(end), _, _) | (None, Some(end), _) => get_end_date(tz, start, end),
+        (None, None, Some(duration)) => add_duration(start, parse_duration(duration)?),
+        (None, None, None) => add_duration(start, default_duration),
+    }
+}
+
+/// Adds a duration to the start of an event, or returns an error if the end is out of range.
+fn add_duration(start: DateTime<Utc>, duration: Duration) -> Result<DateTime<Utc>> {
+    start.checked_add_signed(duration).ok_or_else(|| {
+        anyhow!(GcalError::Parse(
+            "The end of the event is out of range".to_string()
+        ))
+    })
+}
+
+/// Builds the recurrence rule given by `--repeat` and the related options.
+fn get_recurrence(
+    repeat: &str,
+    matches: &ArgMatches,
+    first_occurrence: NaiveDateTime,
+) -> Result<Recurrence> {
+    let weekdays = match matches.get_one::<String>("on") {
+        Some(on) => parse_weekdays(on)?,
+        None => vec![],
+    };
+    let until = matches
+        .get_one::<String>("until")
+        .map(|until| get_naive_date_from_string(until))
+        .transpose()?;
+    Recurrence::new(
+        repeat.parse::<Frequency>()?,
+        matches.get_one::<u32>("every").copied().unwrap_or(1),
+        weekdays,
+        until,
+        matches.get_one::<u32>("count").copied(),
+        first_occurrence.date(),
+    )
+}
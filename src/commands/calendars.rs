@@ -0,0 +1,242 @@
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
+use google_calendar3::api::{Calendar, CalendarListEntry};
+
+use crate::util::calendar::{get_calendar_list, resolve_calendar_ids, Hub};
+use crate::util::error::GcalError;
+use crate::util::prompt;
+
+/// Builds the `calendars` subcommand and its own subcommands.
+pub fn command() -> Command {
+    let calendar_arg = || Arg::new("name").help("Calendar ID or name").required(true);
+    Command::new("calendars")
+        .about("Manages the calendars of your Google Calendar")
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("Lists the calendars in your calendar list"))
+        .subcommand(
+            Command::new("create")
+                .about("Creates a new calendar")
+                .arg(
+                    Arg::new("name")
+                        .help("Sets the calendar name")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("timezone")
+                        .help("Sets the calendar time zone, e.g. Europe/Budapest")
+                        .long("timezone"),
+                )
+                .arg(
+                    Arg::new("description")
+                        .help("Sets the calendar description")
+                        .long("description"),
+                ),
+        )
+        .subcommand(
+            Command::new("rename")
+                .about("Renames a calendar")
+                .arg(calendar_arg())
+                .arg(
+                    Arg::new("new_name")
+                        .help("Sets the new calendar name")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("delete")
+                .about("Deletes a calendar you own, with all of its events")
+                .arg(calendar_arg())
+                .arg(
+                    Arg::new("yes")
+                        .help("Deletes without asking for confirmation")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("subscribe")
+                .about("Adds an existing calendar to your calendar list")
+                .arg(
+                    Arg::new("name")
+                        .help("Calendar ID, e.g. en.usa#holiday@group.v.calendar.google.com")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("unsubscribe")
+                .about("Removes a calendar from your calendar list")
+                .arg(calendar_arg()),
+        )
+}
+
+/// Runs the selected `calendars` subcommand.
+pub async fn run(hub: &Hub, matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("create", matches)) => {
+            let calendar = Calendar {
+                summary: matches.get_one::<String>("name").cloned(),
+                time_zone: matches.get_one::<String>("timezone").cloned(),
+                description: matches.get_one::<String>("description").cloned(),
+                ..Default::default()
+            };
+            let (_, calendar) = hub
+                .calendars()
+                .insert(calendar)
+                .doit()
+                .await
+                .context("Failed to create calendar")?;
+            println!("Calendar created: {}", calendar.id.unwrap_or_default());
+        }
+        Some(("rename", matches)) => {
+            let calendar_id = resolve_calendar_id(hub, matches).await?;
+            let calendar = Calendar {
+                summary: matches.get_one::<String>("new_name").cloned(),
+                ..Default::default()
+            };
+            hub.calendars()
+                .patch(calendar, &calendar_id)
+                .doit()
+                .await
+                .with_context(|| format!("Failed to rename calendar {}", calendar_id))?;
+            println!("Calendar renamed.");
+        }
+        Some(("delete", matches)) => {
+            let calendar_id = resolve_calendar_id(hub, matches).await?;
+            let question = format!("Delete calendar {} and all of its events?", calendar_id);
+            if !matches.get_flag("yes") && !prompt::confirm(&question)? {
+                println!("Cancelled.");
+                return Ok(());
+            }
+            hub.calendars()
+                .delete(&calendar_id)
+                .doit()
+                .await
+                .with_context(|| format!("Failed to delete calendar {}", calendar_id))?;
+            println!("Calendar deleted.");
+        }
+        Some(("subscribe", matches)) => {
+            let entry = CalendarListEntry {
+                id: matches.get_one::<String>("name").cloned(),
+                ..Default::default()
+            };
+            let (_, entry) = hub
+                .calendar_list()
+                .insert(entry)
+                .doit()
+                .await
+                .context("Failed to subscribe to calendar")?;
+            println!(
+                "Subscribed to calendar: {}",
+                entry.summary.or(entry.id).unwrap_or_default()
+            );
+        }
+        Some(("unsubscribe", matches)) => {
+            let calendar_id = resolve_calendar_id(hub, matches).await?;
+            hub.calendar_list()
+                .delete(&calendar_id)
+                .doit()
+                .await
+                .with_context(|| format!("Failed to unsubscribe from calendar {}", calendar_id))?;
+            println!("Unsubscribed from calendar.");
+        }
+        _ => {
+            let calendar_list = get_calendar_list(hub).await?;
+            println!("{}", render_calendar_table(&calendar_list));
+        }
+    }
+    Ok(())
+}
+
+async fn resolve_calendar_id(hub: &Hub, matches: &ArgMatches) -> Result<String> {
+    let name = matches
+        .get_one::<String>("name")
+        .ok_or_else(|| anyhow!("No calendar given"))?;
+    resolve_calendar_ids(hub, std::slice::from_ref(name))
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            anyhow!(GcalError::NotFound(format!(
+                "Calendar '{}' not found",
+                name
+            )))
+        })
+}
+
+/// Renders the calendar list as a table with access role, colour, time zone and visibility.
+fn render_calendar_table(calendar_list: &[CalendarListEntry]) -> Table {
+    let mut table = Table::new();
+    table
+        .set_header(
+            [
+                "Name",
+                "ID",
+                "Access",
+                "Colour",
+                "Time zone",
+                "Hidden",
+                "Selected",
+            ]
+            .map(|title| Cell::new(title).add_attribute(Attribute::Bold)),
+        )
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    let yes_no = |value: Option<bool>| if value == Some(true) { "yes" } else { "no" };
+    for entry in calendar_list {
+        let mut name = entry
+            .summary_override
+            .as_ref()
+            .or(entry.summary.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        if entry.primary == Some(true) {
+            name.push_str(" (primary)");
+        }
+        let colour = entry.background_color.clone().unwrap_or_default();
+        let colour_cell = match parse_hex_color(&colour) {
+            Some(color) => Cell::new(&colour).fg(color),
+            None => Cell::new(&colour),
+        };
+        table.add_row(vec![
+            Cell::new(name),
+            Cell::new(entry.id.as_deref().unwrap_or_default()),
+            Cell::new(entry.access_role.as_deref().unwrap_or_default()),
+            colour_cell,
+            Cell::new(entry.time_zone.as_deref().unwrap_or_default()),
+            Cell::new(yes_no(entry.hidden)),
+            Cell::new(yes_no(entry.selected)),
+        ]);
+    }
+    table
+}
+
+/// Parses a `#rrggbb` colour as used by the Calendar API.
+fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::Rgb {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(
+            parse_hex_color("#9fe1e7"),
+            Some(Color::Rgb {
+                r: 0x9f,
+                g: 0xe1,
+                b: 0xe7
+            })
+        );
+        assert_eq!(parse_hex_color("9fe1e7"), None);
+        assert_eq!(parse_hex_color("#zzzzzz"), None);
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use clap::{Arg, ArgMatches, Command};
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
+
+use crate::util::config::{get_config_path, Config, CONFIG_KEYS};
+use crate::util::error::GcalError;
+
+/// Builds the `config` subcommand and its own subcommands.
+pub fn command() -> Command {
+    let key_arg = || {
+        Arg::new("key")
+            .help("Configuration key")
+            .value_parser(CONFIG_KEYS.map(|(key, _)| key))
+            .required(true)
+    };
+    Command::new("config")
+        .about("Manages the defaults stored in ~/.gcal/config.toml")
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("Lists every configuration key with its value"))
+        .subcommand(
+            Command::new("get")
+                .about("Prints the value of a configuration key")
+                .arg(key_arg()),
+        )
+        .subcommand(
+            Command::new("set")
+                .about("Sets the value of a configuration key")
+                .arg(key_arg())
+                .arg(Arg::new("value").help("Sets the new value").required(true)),
+        )
+        .subcommand(
+            Command::new("unset")
+                .about("Removes a configuration key, restoring its default")
+                .arg(key_arg()),
+        )
+}
+
+/// Runs the selected `config` subcommand. It works offline, without connecting to Google.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let path = get_config_path()?;
+    let mut config = Config::load(&path)?;
+    let key = |matches: &ArgMatches| {
+        matches
+            .get_one::<String>("key")
+            .cloned()
+            .unwrap_or_default()
+    };
+    match matches.subcommand() {
+        Some(("get", matches)) => match config.get(&key(matches))? {
+            Some(value) => println!("{}", value),
+            None => bail!(GcalError::NotFound(format!(
+                "'{}' is not set",
+                key(matches)
+            ))),
+        },
+        Some(("set", matches)) => {
+            let value = matches
+                .get_one::<String>("value")
+                .map_or("", String::as_str);
+            config.set(&key(matches), value)?;
+            config.save(&path)?;
+            println!("Set {} in {}", key(matches), path.display());
+        }
+        Some(("unset", matches)) => {
+            config.unset(&key(matches))?;
+            config.save(&path)?;
+            println!("Unset {} in {}", key(matches), path.display());
+        }
+        _ => println!("{}", render_config_table(&config)?),
+    }
+    Ok(())
+}
+
+/// Renders every configuration key with its value and description.
+fn render_config_table(config: &Config) -> Result<Table> {
+    let mut table = Table::new();
+    table
+        .set_header(
+            ["Key", "Value", "Description"]
+                .map(|title| Cell::new(title).add_attribute(Attribute::Bold)),
+        )
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for (key, description) in CONFIG_KEYS {
+        let value = match config.get(key)? {
+            Some(value) => Cell::new(value),
+            None => Cell::new("(default)").fg(Color::DarkGrey),
+        };
+        table.add_row(vec![Cell::new(key), value, Cell::new(description)]);
+    }
+    Ok(table)
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Context, Result};
+use chrono_tz::Tz;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use google_calendar3::api::Event;
+
+use super::{event_selector_args, resolve_event};
+use crate::util::calendar::Hub;
+use crate::util::error::GcalError;
+use crate::util::event::{format_event_line, is_recurring};
+use crate::util::prompt;
+use crate::util::recurrence::{get_until_before, truncate_recurrence};
+
+/// Builds the `delete` subcommand.
+pub fn command() -> Command {
+    Command::new("delete")
+        .about("Deletes an event from Google Calendar")
+        .args(event_selector_args())
+        .arg(
+            Arg::new("yes")
+                .help("Deletes without asking for confirmation")
+                .long("yes")
+                .short('y')
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("scope")
+                .help("Which occurrences of a recurring event to delete")
+                .long("scope")
+                .value_parser(["this", "following", "all"])
+                .default_value("this"),
+        )
+}
+
+/// Deletes the selected event, after confirmation unless `--yes` is given.
+///
+/// For recurring events, `--scope this` deletes the selected occurrence only,
+/// `--scope following` ends the series right before it and `--scope all` deletes the whole series.
+pub async fn run(hub: &Hub, tz: Tz, calendar_id: &str, matches: &ArgMatches) -> Result<()> {
+    let yes = matches.get_flag("yes");
+    let scope = matches
+        .get_one::<String>("scope")
+        .map(String::as_str)
+        .unwrap_or("this");
+    let event = resolve_event(hub, tz, calendar_id, matches, !yes).await?;
+    let event_id = event
+        .id
+        .clone()
+        .ok_or_else(|| anyhow!("The event has no ID"))?;
+
+    let scope = if is_recurring(&event) { scope } else { "" };
+    let occurrences = match scope {
+        "all" => "all occurrences of ",
+        "following" => "this and all following occurrences of ",
+        "this" => "this occurrence of ",
+        _ => "",
+    };
+    let question = format!("Delete {}{}?", occurrences, format_event_line(&event, tz));
+    if !yes && !prompt::confirm(&question)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    match scope {
+        "all" => {
+            let series_id = event.recurring_event_id.as_deref().unwrap_or(&event_id);
+            delete_event(hub, calendar_id, series_id).await?;
+        }
+        "following" => delete_following(hub, calendar_id, &event, &event_id).await?,
+        _ => {
+            if event.recurrence.is_some() {
+                return Err(anyhow!(GcalError::Parse(format!(
+                    "'{}' is a whole recurring series, use --scope all or select a single occurrence",
+                    event_id
+                ))));
+            }
+            delete_event(hub, calendar_id, &event_id).await?;
+        }
+    }
+    println!("Event deleted.");
+    Ok(())
+}
+
+async fn delete_event(hub: &Hub, calendar_id: &str, event_id: &str) -> Result<()> {
+    hub.events()
+        .delete(calendar_id, event_id)
+        .doit()
+        .await
+        .with_context(|| format!("Failed to delete event {}", event_id))?;
+    Ok(())
+}
+
+/// Ends the series of a recurring event right before the selected occurrence.
+///
+/// If the occurrence is the first one of the series, the whole series is deleted.
+async fn delete_following(
+    hub: &Hub,
+    calendar_id: &str,
+    event: &Event,
+    event_id: &str,
+) -> Result<()> {
+    let series_id = event.recurring_event_id.as_deref().unwrap_or(event_id);
+    let (_, series) = hub
+        .events()
+        .get(calendar_id, series_id)
+        .doit()
+        .await
+        .with_context(|| format!("Failed to get recurring event {}", series_id))?;
+
+    let occurrence_start = event
+        .original_start_time
+        .as_ref()
+        .or(event.start.as_ref())
+        .ok_or_else(|| anyhow!("The event has no start time"))?;
+    let is_first = series.start.as_ref().is_some_and(|start| {
+        start.date_time == occurrence_start.date_time && start.date == occurrence_start.date
+    });
+    if is_first {
+        return delete_event(hub, calendar_id, series_id).await;
+    }
+
+    let until =
+        get_until_before(occurrence_start).ok_or_else(|| anyhow!("The event has no start time"))?;
+    let recurrence = truncate_recurrence(&series.recurrence.unwrap_or_default(), &until);
+    let patch = Event {
+        recurrence: Some(recurrence),
+        ..Default::default()
+    };
+    hub.events()
+        .patch(patch, calendar_id, series_id)
+        .doit()
+        .await
+        .with_context(|| format!("Failed to update recurring event {}", series_id))?;
+    Ok(())
+}
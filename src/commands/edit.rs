@@ -0,0 +1,137 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Duration;
+use chrono_tz::Tz;
+use clap::{Arg, ArgMatches, Command};
+use google_calendar3::api::{Event, EventDateTime};
+
+use super::{attendee_args, event_selector_args, get_attendees, resolve_event};
+use crate::util::calendar::Hub;
+use crate::util::date::{get_date_from_string, get_end_date};
+use crate::util::error::GcalError;
+
+/// Builds the `edit` subcommand.
+pub fn command() -> Command {
+    Command::new("edit")
+        .about("Edits an existing event in Google Calendar")
+        .args(event_selector_args())
+        .arg(
+            Arg::new("title")
+                .help("Sets the new event title")
+                .long("title")
+                .short('t'),
+        )
+        .arg(
+            Arg::new("start")
+                .help("Moves the event to the given start date, keeping its duration")
+                .long("start")
+                .short('s'),
+        )
+        .arg(
+            Arg::new("end")
+                .help("Sets the new end, as a time on the start day (e.g. 16:30 or 4pm) or a full date")
+                .long("end")
+                .short('e'),
+        )
+        .arg(
+            Arg::new("location")
+                .help("Sets the new event location")
+                .long("location")
+                .short('l'),
+        )
+        .arg(
+            Arg::new("description")
+                .help("Sets the new event description")
+                .long("description"),
+        )
+        .args(attendee_args())
+}
+
+/// Patches the selected event with the given fields.
+///
+/// Only the fields passed on the command line are sent to the API, everything else is left
+/// untouched. When only the start is moved, the end is moved along with it so that the event
+/// keeps its original duration. Invited attendees are added to the existing ones.
+pub async fn run(hub: &Hub, tz: Tz, calendar_id: &str, matches: &ArgMatches) -> Result<()> {
+    let has_changes = [
+        "title",
+        "start",
+        "end",
+        "location",
+        "description",
+        "invite",
+        "optional",
+    ]
+    .iter()
+    .any(|id| matches.contains_id(id));
+    if !has_changes {
+        bail!(GcalError::Parse(
+            "Nothing to change, use --title, --start, --end, --location, --description, --invite or --optional"
+                .to_string()
+        ));
+    }
+    let mut patch = Event {
+        summary: matches.get_one::<String>("title").cloned(),
+        location: matches.get_one::<String>("location").cloned(),
+        description: matches.get_one::<String>("description").cloned(),
+        ..Default::default()
+    };
+    let start = matches.get_one::<String>("start");
+    let end = matches.get_one::<String>("end");
+
+    let event = resolve_event(hub, tz, calendar_id, matches, true).await?;
+    let event_id = event
+        .id
+        .as_deref()
+        .ok_or_else(|| anyhow!("The event has no ID"))?;
+
+    patch.attendees = get_attendees(matches, event.attendees.clone())?;
+    if start.is_some() || end.is_some() {
+        if event
+            .start
+            .as_ref()
+            .is_some_and(|start| start.date.is_some())
+        {
+            bail!(GcalError::Parse(
+                "Changing the time of all-day events is not supported".to_string()
+            ));
+        }
+        let current_start = event.start.as_ref().and_then(|start| start.date_time);
+        let current_end = event.end.as_ref().and_then(|end| end.date_time);
+        let new_start = match start {
+            Some(start) => get_date_from_string(tz, start)?,
+            None => current_start
+                .ok_or_else(|| anyhow!("The event has no start time, use --start to set one"))?,
+        };
+        let duration = match (current_start, current_end) {
+            (Some(current_start), Some(current_end)) => current_end - current_start,
+            _ => Duration::hours(1),
+        };
+        let new_end = match end {
+            Some(end) => get_end_date(tz, new_start, end)?,
+            None => new_start.checked_add_signed(duration).ok_or_else(|| {
+                anyhow!(GcalError::Parse(
+                    "The end of the event is out of range".to_string()
+                ))
+            })?,
+        };
+        patch.start = Some(EventDateTime {
+            date_time: Some(new_start),
+            ..Default::default()
+        });
+        patch.end = Some(EventDateTime {
+            date_time: Some(new_end),
+            ..Default::default()
+        });
+    }
+
+    let mut call = hub.events().patch(patch, calendar_id, event_id);
+    if let Some(notify) = matches.get_one::<String>("notify") {
+        call = call.send_updates(notify);
+    }
+    let (_, event) = call
+        .doit()
+        .await
+        .with_context(|| format!("Failed to update event {}", event_id))?;
+    println!("Event updated: {}", event.html_link.unwrap_or_default());
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::fs;
+
+use anyhow::{Context, Result};
+use chrono::{Local, Utc};
+use chrono_tz::Tz;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use crate::util::calendar::{list_event_series, Hub};
+use crate::util::config::Config;
+use crate::util::date::{
+    get_date_range, get_day_bounds, get_naive_date_from_string, get_start_of_the_week,
+};
+use crate::util::ics::write_calendar;
+
+/// Builds the `export` subcommand.
+pub fn command() -> Command {
+    Command::new("export")
+        .about("Exports events to a file for other calendar applications")
+        .arg(
+            Arg::new("ics")
+                .help("Exports an iCalendar (.ics) file")
+                .long("ics")
+                .action(ArgAction::SetTrue)
+                .required(true),
+        )
+        .arg(
+            Arg::new("from")
+                .help("Sets the first day of the export (YYYY-MM-DD or MM-DD)")
+                .long("from"),
+        )
+        .arg(
+            Arg::new("to")
+                .help("Sets the last day of the export (YYYY-MM-DD or MM-DD)")
+                .long("to")
+                .conflicts_with("days"),
+        )
+        .arg(
+            Arg::new("days")
+                .help("Exports the given number of days, starting today or at --from")
+                .long("days")
+                .value_parser(value_parser!(i64).range(1..)),
+        )
+        .arg(
+            Arg::new("output")
+                .help("Writes the file to this path instead of the standard output")
+                .long("output")
+                .short('o'),
+        )
+}
+
+/// Exports the events of the selected date range of every given calendar.
+///
+/// The date range is selected as in `list`, defaulting to the current week. Recurring events
+/// overlapping the range are exported whole, with their rules, so that other applications
+/// can expand them. An event found in several calendars is exported once.
+pub async fn run(
+    hub: &Hub,
+    tz: Tz,
+    calendar_ids: &[String],
+    config: &Config,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let parse_date = |id: &str| {
+        matches
+            .get_one::<String>(id)
+            .map(|date| get_naive_date_from_string(date))
+            .transpose()
+    };
+    let (start_date, end_date) = get_date_range(
+        get_start_of_the_week(config.week_start()).date_naive(),
+        Local::now().date_naive(),
+        parse_date("from")?,
+        parse_date("to")?,
+        None,
+        matches.get_one::<i64>("days").copied(),
+    )?;
+    let (time_min, _) = get_day_bounds(tz, start_date);
+    let (time_max, _) = get_day_bounds(tz, end_date);
+
+    let mut events = vec![];
+    let mut exported = HashSet::new();
+    for calendar_id in calendar_ids {
+        for event in list_event_series(hub, calendar_id, time_min, time_max).await? {
+            let key = (
+                event.i_cal_uid.clone().or_else(|| event.id.clone()),
+                event
+                    .original_start_time
+                    .as_ref()
+                    .map(|time| (time.date_time, time.date)),
+            );
+            if exported.insert(key) {
+                events.push(event);
+            }
+        }
+    }
+
+    let calendar = write_calendar(&events, tz, Utc::now());
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            fs::write(path, calendar).with_context(|| format!("Failed to write {}", path))?;
+            let count = events
+                .iter()
+                .filter(|event| event.status.as_deref() != Some("cancelled"))
+                .count();
+            println!("Exported {} events to {}", count, path);
+        }
+        None => print!("{}", calendar),
+    }
+    Ok(())
+}
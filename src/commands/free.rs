@@ -0,0 +1,252 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, DurationRound, Local, Utc};
+use chrono_tz::Tz;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+use google_calendar3::api::{Event, EventAttendee, EventDateTime};
+use google_calendar3::api::{FreeBusyRequest, FreeBusyRequestItem};
+
+use crate::util::attendee::parse_emails;
+use crate::util::calendar::Hub;
+use crate::util::config::Config;
+use crate::util::date::{get_day_bounds, parse_duration, parse_period, parse_time_range};
+use crate::util::error::GcalError;
+use crate::util::event::new_conference_data;
+use crate::util::freebusy::{Interval, SlotSearch};
+use crate::util::prompt;
+
+/// Slots never start at odd minutes, but on the next multiple of this many minutes.
+const SLOT_GRANULARITY_MINUTES: i64 = 15;
+
+/// Builds the `free` subcommand.
+pub fn command() -> Command {
+    Command::new("free")
+        .about("Finds free time slots shared with other people")
+        .arg(
+            Arg::new("with")
+                .help("Also checks the calendars of these people, e.g. alice@x.com,bob@y.com")
+                .long("with")
+                .short('w')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("duration")
+                .help("Sets the minimum length of a slot, e.g. 30m, 1h30m or 90 (minutes)")
+                .long("duration")
+                .default_value("30m"),
+        )
+        .arg(
+            Arg::new("within")
+                .help("Sets the days searched, e.g. today, tomorrow, this week, next week or next 3 days")
+                .long("within")
+                .default_value("next 7 days"),
+        )
+        .arg(
+            Arg::new("working_hours")
+                .help("Sets the part of each day searched, 09:00-17:00 unless configured otherwise")
+                .long("working-hours"),
+        )
+        .arg(
+            Arg::new("weekends")
+                .help("Searches Saturdays and Sundays too")
+                .long("weekends")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("limit")
+                .help("Shows at most the given number of slots")
+                .long("limit")
+                .short('n')
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("book")
+                .help("Books one of the slots as an event with this title, inviting --with")
+                .long("book"),
+        )
+}
+
+/// Prints the slots in which every given calendar and person is free, and optionally books
+/// one of them.
+///
+/// The busy times are read with a single free/busy query. Calendars which cannot be read,
+/// e.g. because they are not shared, are reported and left out of the search. A booked event
+/// gets the configured conference and reminders.
+pub async fn run(
+    hub: &Hub,
+    tz: Tz,
+    calendar_ids: &[String],
+    config: &Config,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let get_value = |id: &str| matches.get_one::<String>(id).map_or("", String::as_str);
+    let people = parse_emails(matches.get_many::<String>("with").unwrap_or_default())?;
+    let (first_day, end_day) = parse_period(
+        Local::now().date_naive(),
+        config.week_start(),
+        get_value("within"),
+    )?;
+    let search = SlotSearch {
+        first_day,
+        end_day,
+        working_hours: match matches.get_one::<String>("working_hours") {
+            Some(working_hours) => parse_time_range(working_hours)?,
+            None => config.working_hours()?,
+        },
+        weekends: matches.get_flag("weekends"),
+        duration: parse_duration(get_value("duration"))?,
+    };
+
+    let (time_min, _) = get_day_bounds(tz, first_day);
+    let (time_max, _) = get_day_bounds(tz, end_day);
+    let busy = get_busy_intervals(hub, calendar_ids, &people, time_min, time_max).await?;
+
+    let now = Utc::now();
+    let not_before = now
+        .duration_trunc(Duration::minutes(SLOT_GRANULARITY_MINUTES))
+        .map_or(now, |start| {
+            start + Duration::minutes(SLOT_GRANULARITY_MINUTES)
+        });
+    let mut slots = search.find_free_slots(tz, &busy, not_before);
+    slots.truncate(
+        matches
+            .get_one::<usize>("limit")
+            .copied()
+            .unwrap_or(slots.len()),
+    );
+    if slots.is_empty() {
+        bail!(GcalError::NotFound(
+            "No free slot found, try a shorter --duration or a longer --within".to_string()
+        ));
+    }
+
+    let Some(title) = matches.get_one::<String>("book") else {
+        println!("{}", render_slots(tz, &slots));
+        return Ok(());
+    };
+    let options: Vec<String> = slots
+        .iter()
+        .map(|(start, end)| format_slot(tz, *start, *end))
+        .collect();
+    let Some(index) = prompt::pick("Select a slot (number): ", &options)? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let start = slots[index].0;
+    let defaults = config.event_defaults()?;
+    let event = Event {
+        summary: Some(title.to_string()),
+        start: Some(EventDateTime {
+            date_time: Some(start),
+            ..Default::default()
+        }),
+        end: Some(EventDateTime {
+            date_time: Some(start + search.duration),
+            ..Default::default()
+        }),
+        attendees: Some(
+            people
+                .iter()
+                .map(|email| EventAttendee {
+                    email: Some(email.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+        .filter(|attendees: &Vec<EventAttendee>| !attendees.is_empty()),
+        conference_data: defaults.conference.then(new_conference_data),
+        reminders: defaults.reminders,
+        ..Default::default()
+    };
+    let (_, event) = hub
+        .events()
+        .insert(event, &calendar_ids[0])
+        .conference_data_version(1)
+        .send_updates("all")
+        .doit()
+        .await
+        .context("Failed to create event")?;
+    println!("Event created: {}", event.html_link.unwrap_or_default());
+    Ok(())
+}
+
+/// Queries the busy intervals of the given calendars and people between two instants.
+async fn get_busy_intervals(
+    hub: &Hub,
+    calendar_ids: &[String],
+    people: &[String],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+) -> Result<Vec<Interval>> {
+    let request = FreeBusyRequest {
+        time_min: Some(time_min),
+        time_max: Some(time_max),
+        items: Some(
+            calendar_ids
+                .iter()
+                .chain(people)
+                .map(|id| FreeBusyRequestItem {
+                    id: Some(id.to_string()),
+                })
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    let (_, response) = hub
+        .freebusy()
+        .query(request)
+        .doit()
+        .await
+        .context("Failed to query free/busy information")?;
+
+    let mut busy = vec![];
+    for (id, calendar) in response.calendars.unwrap_or_default() {
+        if let Some(error) = calendar.errors.unwrap_or_default().first() {
+            eprintln!(
+                "Warning: no free/busy information for {} ({})",
+                id,
+                error.reason.as_deref().unwrap_or("unknown error")
+            );
+            continue;
+        }
+        busy.extend(
+            calendar
+                .busy
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|period| Some((period.start?, period.end?))),
+        );
+    }
+    Ok(busy)
+}
+
+fn format_slot(tz: Tz, start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    let (start, end) = (start.with_timezone(&tz), end.with_timezone(&tz));
+    format!(
+        "{} {} - {}",
+        start.format("%a %Y-%m-%d"),
+        start.format("%H:%M"),
+        end.format("%H:%M")
+    )
+}
+
+/// Renders the free slots as a table of days and times in the given timezone.
+fn render_slots(tz: Tz, slots: &[Interval]) -> Table {
+    let mut table = Table::new();
+    table
+        .set_header(
+            ["Day", "From", "To", "Free for"]
+                .map(|title| Cell::new(title).add_attribute(Attribute::Bold)),
+        )
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for (start, end) in slots {
+        let length = *end - *start;
+        table.add_row(vec![
+            start.with_timezone(&tz).format("%a %Y-%m-%d").to_string(),
+            start.with_timezone(&tz).format("%H:%M").to_string(),
+            end.with_timezone(&tz).format("%H:%M").to_string(),
+            format!("{}h{:02}m", length.num_hours(), length.num_minutes() % 60),
+        ]);
+    }
+    table
+}
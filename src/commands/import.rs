@@ -0,0 +1,351 @@
+use std::fs;
+use std::io::{self, Read};
+
+use anyhow::{anyhow, Context, Result};
+use chrono_tz::Tz;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use google_calendar3::api::{Event, EventDateTime};
+use uuid::Uuid;
+
+use crate::util::calendar::Hub;
+use crate::util::event::format_event_line;
+use crate::util::ics::parse_calendar;
+
+/// Builds the `import` subcommand.
+pub fn command() -> Command {
+    Command::new("import")
+        .about("Imports the events of an iCalendar (.ics) file")
+        .arg(
+            Arg::new("file")
+                .help("Sets the file to import, or - to read the standard input")
+                .required(true),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .help("Shows what would be imported without changing the calendar")
+                .long("dry-run")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Imports the events of an iCalendar file into the calendar.
+///
+/// Events are matched by their iCalendar UID: an event already in the calendar is updated
+/// instead of duplicated, so a file can be imported again after it changed. Events without a
+/// UID get one derived from their start and title, see `assign_uids`. Modified occurrences
+/// of a recurring event are applied to the matching occurrence once the series is imported.
+pub async fn run(hub: &Hub, tz: Tz, calendar_id: &str, matches: &ArgMatches) -> Result<()> {
+    let path = matches
+        .get_one::<String>("file")
+        .map_or("-", String::as_str);
+    let input = if path == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read the standard input")?;
+        input
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?
+    };
+    let mut events = parse_calendar(&input, tz)?;
+    assign_uids(&mut events);
+    let events = deduplicate(events);
+    let dry_run = matches.get_flag("dry_run");
+
+    let (mut created, mut updated) = (0, 0);
+    let (exceptions, series): (Vec<Event>, Vec<Event>) = events
+        .into_iter()
+        .partition(|event| event.original_start_time.is_some());
+    let mut imported: Vec<(String, String)> = vec![];
+    for mut event in series {
+        let uid = event.i_cal_uid.clone().unwrap_or_default();
+        prepare_for_import(&mut event);
+
+        let (_, existing) = hub
+            .events()
+            .list(calendar_id)
+            .i_cal_uid(&uid)
+            .doit()
+            .await
+            .with_context(|| format!("Failed to look up event {}", uid))?;
+        let exists = !existing.items.unwrap_or_default().is_empty();
+        let action = match (exists, dry_run) {
+            (true, true) => "Would update",
+            (true, false) => "Updated",
+            (false, true) => "Would create",
+            (false, false) => "Created",
+        };
+        if exists {
+            updated += 1;
+        } else {
+            created += 1;
+        }
+        let line = format_event_line(&event, tz);
+        if !dry_run {
+            let (_, event) = hub
+                .events()
+                .import(event, calendar_id)
+                .doit()
+                .await
+                .with_context(|| format!("Failed to import event {}", uid))?;
+            imported.push((uid, event.id.unwrap_or_default()));
+        }
+        println!("{}: {}", action, line);
+    }
+
+    for exception in exceptions {
+        let line = format_event_line(&exception, tz);
+        let series_id = imported
+            .iter()
+            .find(|(uid, _)| Some(uid) == exception.i_cal_uid.as_ref())
+            .map(|(_, id)| id.as_str());
+        match (series_id, dry_run) {
+            (_, true) => println!("Would change occurrence: {}", line),
+            (None, false) => eprintln!(
+                "Warning: skipping changed occurrence without its series: {}",
+                line
+            ),
+            (Some(series_id), false) => {
+                import_exception(hub, calendar_id, series_id, exception).await?;
+                println!("Changed occurrence: {}", line);
+            }
+        }
+    }
+
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!(
+        "{} {} events ({} new, {} updated).",
+        verb,
+        created + updated,
+        created,
+        updated
+    );
+    Ok(())
+}
+
+/// Gives every event without a UID one derived from its start and title, so that importing
+/// the same file again updates these events instead of duplicating them. Events with the same
+/// start and title are told apart by their order in the file.
+fn assign_uids(events: &mut [Event]) {
+    let mut seen: Vec<String> = vec![];
+    for event in events.iter_mut().filter(|event| event.i_cal_uid.is_none()) {
+        let start = event.start.as_ref().map_or(String::new(), |start| {
+            match (start.date_time, start.date) {
+                (Some(date_time), _) => date_time.to_rfc3339(),
+                (None, Some(date)) => date.to_string(),
+                _ => String::new(),
+            }
+        });
+        let key = format!(
+            "{}\n{}",
+            start,
+            event.summary.as_deref().unwrap_or_default()
+        );
+        let position = seen.iter().filter(|other| **other == key).count();
+        let name = format!("{}\n{}", key, position);
+        let uid = Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes());
+        event.i_cal_uid = Some(format!("{}@gcal", uid));
+        seen.push(key);
+    }
+}
+
+/// Keeps a single event per UID and occurrence, the last one in the file.
+fn deduplicate(events: Vec<Event>) -> Vec<Event> {
+    let key = |event: &Event| {
+        (
+            event.i_cal_uid.clone(),
+            event
+                .original_start_time
+                .as_ref()
+                .map(|time| (time.date_time, time.date)),
+        )
+    };
+    let mut unique: Vec<Event> = vec![];
+    for event in events {
+        match unique
+            .iter_mut()
+            .find(|other| event.i_cal_uid.is_some() && key(other) == key(&event))
+        {
+            Some(other) => *other = event,
+            None => unique.push(event),
+        }
+    }
+    unique
+}
+
+/// Drops the fields which cannot be imported and sets the time zone recurring events need.
+fn prepare_for_import(event: &mut Event) {
+    event.hangout_link = None;
+    if event.recurrence.is_some() {
+        for time in [&mut event.start, &mut event.end].into_iter().flatten() {
+            if time.date_time.is_some() && time.time_zone.is_none() {
+                time.time_zone = Some("UTC".to_string());
+            }
+        }
+    }
+}
+
+/// Applies a modified or cancelled occurrence to the matching instance of an imported series.
+async fn import_exception(
+    hub: &Hub,
+    calendar_id: &str,
+    series_id: &str,
+    exception: Event,
+) -> Result<()> {
+    let original_start = match exception.original_start_time {
+        Some(EventDateTime {
+            date_time: Some(date_time),
+            ..
+        }) => date_time.to_rfc3339(),
+        Some(EventDateTime {
+            date: Some(date), ..
+        }) => date.to_string(),
+        _ => return Ok(()),
+    };
+    let (_, instances) = hub
+        .events()
+        .instances(calendar_id, series_id)
+        .original_start(&original_start)
+        .doit()
+        .await
+        .with_context(|| format!("Failed to find the occurrence of {}", original_start))?;
+    let instance_id = instances
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|instance| instance.id)
+        .ok_or_else(|| anyhow!("The series has no occurrence at {}", original_start))?;
+
+    if exception.status.as_deref() == Some("cancelled") {
+        hub.events()
+            .delete(calendar_id, &instance_id)
+            .doit()
+            .await
+            .with_context(|| format!("Failed to cancel occurrence {}", instance_id))?;
+        return Ok(());
+    }
+    let patch = Event {
+        summary: exception.summary,
+        description: exception.description,
+        location: exception.location,
+        start: exception.start,
+        end: exception.end,
+        status: exception.status,
+        ..Default::default()
+    };
+    hub.events()
+        .patch(patch, calendar_id, &instance_id)
+        .doit()
+        .await
+        .with_context(|| format!("Failed to change occurrence {}", instance_id))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn event(uid: Option<&str>, summary: &str, original_hour: Option<u32>) -> Event {
+        Event {
+            i_cal_uid: uid.map(str::to_string),
+            summary: Some(summary.to_string()),
+            original_start_time: original_hour.map(|hour| EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, hour, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let events = vec![
+            event(Some("a"), "First", None),
+            event(Some("a"), "Occurrence", Some(10)),
+            event(None, "No UID", None),
+            event(Some("a"), "Second", None),
+            event(None, "No UID", None),
+            event(Some("a"), "Other occurrence", Some(11)),
+        ];
+
+        let summaries: Vec<String> = deduplicate(events)
+            .into_iter()
+            .filter_map(|event| event.summary)
+            .collect();
+
+        assert_eq!(
+            summaries,
+            vec![
+                "Second",
+                "Occurrence",
+                "No UID",
+                "No UID",
+                "Other occurrence"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assign_uids() {
+        let start = |hour| EventDateTime {
+            date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, hour, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        let events = || {
+            vec![
+                Event {
+                    start: Some(start(10)),
+                    ..event(None, "Standup", None)
+                },
+                Event {
+                    start: Some(start(10)),
+                    ..event(None, "Standup", None)
+                },
+                Event {
+                    start: Some(start(11)),
+                    ..event(None, "Standup", None)
+                },
+                event(Some("a"), "Retro", None),
+            ]
+        };
+        let uids = || {
+            let mut events = events();
+            assign_uids(&mut events);
+            events
+                .into_iter()
+                .map(|event| event.i_cal_uid.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let first = uids();
+
+        assert_eq!(first, uids());
+        assert_ne!(first[0], first[1]);
+        assert_ne!(first[0], first[2]);
+        assert!(first[0].ends_with("@gcal"));
+        assert_eq!(first[3], "a");
+        let mut events = events();
+        assign_uids(&mut events);
+        assert_eq!(deduplicate(events).len(), 4);
+    }
+
+    #[test]
+    fn test_prepare_for_import() {
+        let mut event = Event {
+            start: Some(EventDateTime {
+                date_time: Some(Utc::now()),
+                ..Default::default()
+            }),
+            recurrence: Some(vec!["RRULE:FREQ=DAILY".to_string()]),
+            hangout_link: Some("https://meet.google.com/abc-defg-hij".to_string()),
+            ..Default::default()
+        };
+
+        prepare_for_import(&mut event);
+
+        assert_eq!(event.start.unwrap().time_zone.as_deref(), Some("UTC"));
+        assert_eq!(event.hangout_link, None);
+    }
+}
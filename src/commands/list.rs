@@ -0,0 +1,160 @@
+use anyhow::Result;
+use chrono::{Duration, Local};
+use chrono_tz::Tz;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use super::views::{
+    render_agenda_view, render_day_view, render_month_view, render_week_view, VIEWS,
+};
+use crate::util::attendee::is_pending;
+use crate::util::calendar::{list_events_of_accounts, Account};
+use crate::util::config::Config;
+use crate::util::date::{
+    get_date_range, get_day_bounds, get_month_range, get_naive_date_from_string,
+    get_start_of_the_week,
+};
+use crate::util::output::{format_records, EventRecord, OUTPUT_FORMATS};
+
+/// Builds the `list` subcommand.
+pub fn command() -> Command {
+    Command::new("list")
+        .about("Lists all events in Google Calendar")
+        .arg(
+            Arg::new("format")
+                .help("Sets the output format, table unless configured otherwise")
+                .long("format")
+                .short('f')
+                .value_parser(OUTPUT_FORMATS),
+        )
+        .arg(
+            Arg::new("view")
+                .help("Sets the layout of the table output")
+                .long("view")
+                .short('v')
+                .value_parser(VIEWS)
+                .default_value("week"),
+        )
+        .arg(
+            Arg::new("from")
+                .help("Sets the first day of the listing (YYYY-MM-DD or MM-DD)")
+                .long("from")
+                .conflicts_with("week"),
+        )
+        .arg(
+            Arg::new("to")
+                .help("Sets the last day of the listing (YYYY-MM-DD or MM-DD)")
+                .long("to")
+                .conflicts_with_all(["week", "days"]),
+        )
+        .arg(
+            Arg::new("week")
+                .help("Lists the week relative to the current one, e.g. +1 or -2")
+                .long("week")
+                .short('w')
+                .allow_negative_numbers(true)
+                .value_parser(value_parser!(i64)),
+        )
+        .arg(
+            Arg::new("days")
+                .help("Lists the given number of days, starting today or at --from")
+                .long("days")
+                .value_parser(value_parser!(i64).range(1..)),
+        )
+        .arg(
+            Arg::new("limit")
+                .help("Lists at most the given number of events")
+                .long("limit")
+                .short('n')
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("pending")
+                .help("Lists only the invitations you have not answered yet")
+                .long("pending")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Prints the events of the selected date range of every given calendar, either in the
+/// `--view` layout or as structured records with `--format`.
+///
+/// Without range options, the week view lists the current week, the day view today and
+/// the month view the current month. With `--pending`, only unanswered invitations are kept.
+/// Weeks start on the configured `week_start` day. The events of several accounts, selected
+/// with `--profile`, are merged into a single listing.
+pub async fn run(
+    accounts: &[Account],
+    tz: Tz,
+    config: &Config,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let parse_date = |id: &str| {
+        matches
+            .get_one::<String>(id)
+            .map(|date| get_naive_date_from_string(date))
+            .transpose()
+    };
+    let view = matches
+        .get_one::<String>("view")
+        .map_or("week", String::as_str);
+    let today = Local::now().date_naive();
+    let has_range = ["from", "to", "week", "days"]
+        .iter()
+        .any(|id| matches.contains_id(id));
+    let (start_date, end_date) = match view {
+        "day" if !has_range => (today, today + Duration::days(1)),
+        "month" if !has_range => get_month_range(today),
+        _ => get_date_range(
+            get_start_of_the_week(config.week_start()).date_naive(),
+            today,
+            parse_date("from")?,
+            parse_date("to")?,
+            matches.get_one::<i64>("week").copied(),
+            matches.get_one::<i64>("days").copied(),
+        )?,
+    };
+    let (time_min, _) = get_day_bounds(tz, start_date);
+    let (time_max, _) = get_day_bounds(tz, end_date);
+
+    let limit = matches.get_one::<usize>("limit").copied();
+    let items = if matches.get_flag("pending") {
+        let mut items = list_events_of_accounts(accounts, tz, time_min, time_max, None).await?;
+        items.retain(|(_, event)| is_pending(event));
+        items.truncate(limit.unwrap_or(items.len()));
+        items
+    } else {
+        list_events_of_accounts(accounts, tz, time_min, time_max, limit).await?
+    };
+
+    let format = matches
+        .get_one::<String>("format")
+        .or(config.format.as_ref())
+        .map_or("table", String::as_str);
+    if format != "table" {
+        let records: Vec<EventRecord> = items
+            .iter()
+            .map(|(calendar_id, event)| EventRecord::new(calendar_id, event, tz))
+            .collect();
+        print!("{}", format_records(&records, format)?);
+        return Ok(());
+    }
+
+    match view {
+        "day" => {
+            for table in render_day_view(tz, start_date, end_date, &items) {
+                println!("{table}");
+            }
+        }
+        "month" => println!(
+            "{}",
+            render_month_view(tz, start_date, end_date, config.week_start(), &items)
+        ),
+        "agenda" => println!("{}", render_agenda_view(tz, &items)),
+        _ => {
+            for table in render_week_view(tz, start_date, end_date, &items) {
+                println!("{table}");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,297 @@
+pub mod add;
+pub mod calendars;
+pub mod config;
+pub mod delete;
+pub mod edit;
+pub mod export;
+pub mod free;
+pub mod import;
+pub mod list;
+pub mod next;
+pub mod now;
+pub mod profile;
+pub mod rsvp;
+pub mod search;
+pub mod show;
+pub mod views;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use clap::{Arg, ArgAction, ArgMatches};
+use google_calendar3::api::{Event, EventAttendee};
+use google_calendar3::common;
+
+use crate::util::attendee::{is_declined, merge_attendees, parse_emails, NOTIFY_OPTIONS};
+use crate::util::calendar::{list_events, list_events_of_calendars, Hub};
+use crate::util::date::{get_day_bounds, get_naive_date_from_string};
+use crate::util::error::GcalError;
+use crate::util::event::format_event_line;
+use crate::util::output::{format_template, EventRecord, TEMPLATE_FIELDS};
+use crate::util::prompt;
+
+/// How far back a title search looks when no date is given.
+const SEARCH_DAYS_BEFORE: i64 = 30;
+/// How far ahead a title search looks when no date is given.
+const SEARCH_DAYS_AFTER: i64 = 90;
+
+/// Returns the positional argument and `--date` option used to select an existing event.
+pub fn event_selector_args() -> [Arg; 2] {
+    [
+        Arg::new("event")
+            .help("Event ID, or text to search for in the event titles")
+            .required(true),
+        Arg::new("date")
+            .help("Restricts the title search to the given day (YYYY-MM-DD or MM-DD)")
+            .long("date")
+            .short('d'),
+    ]
+}
+
+/// Returns the `--invite`, `--optional` and `--notify` options used to invite attendees.
+pub fn attendee_args() -> [Arg; 3] {
+    [
+        Arg::new("invite")
+            .help("Invites attendees, e.g. alice@x.com,bob@y.com")
+            .long("invite")
+            .short('i')
+            .action(ArgAction::Append),
+        Arg::new("optional")
+            .help("Invites optional attendees, e.g. carol@z.com")
+            .long("optional")
+            .action(ArgAction::Append),
+        Arg::new("notify")
+            .help("Sets who is notified of the change by email, defaults to none")
+            .long("notify")
+            .value_parser(NOTIFY_OPTIONS),
+    ]
+}
+
+/// Returns the `--format` option of the commands printing one line per event, e.g. for
+/// status bars.
+pub fn template_arg(default: &'static str) -> Arg {
+    Arg::new("format")
+        .help(format!(
+            "Sets the template of each line, with the placeholders {}",
+            TEMPLATE_FIELDS
+                .map(|field| format!("{{{}}}", field))
+                .join(" ")
+        ))
+        .long("format")
+        .short('f')
+        .default_value(default)
+}
+
+/// Lists the timed events of the given calendars between two instants, leaving out
+/// cancelled events and invitations the current user declined.
+pub async fn list_attended_events(
+    hub: &Hub,
+    tz: Tz,
+    calendar_ids: &[String],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+) -> Result<Vec<(String, Event)>> {
+    let mut items =
+        list_events_of_calendars(hub, tz, calendar_ids, time_min, time_max, None, None).await?;
+    items.retain(|(_, event)| {
+        event
+            .start
+            .as_ref()
+            .is_some_and(|start| start.date_time.is_some())
+            && event.status.as_deref() != Some("cancelled")
+            && !is_declined(event)
+    });
+    Ok(items)
+}
+
+/// Prints each event on a line filled from the `--format` template of `template_arg`.
+pub fn print_with_template(
+    tz: Tz,
+    items: &[(String, Event)],
+    matches: &ArgMatches,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let template = matches
+        .get_one::<String>("format")
+        .map_or("{title}", String::as_str);
+    for (calendar_id, event) in items {
+        let record = EventRecord::new(calendar_id, event, tz);
+        println!("{}", format_template(template, &record, event, tz, now)?);
+    }
+    Ok(())
+}
+
+/// Adds the attendees given with `attendee_args` to the current attendees of an event.
+///
+/// ## Returns
+///
+/// * `Result<Option<Vec<EventAttendee>>>` - The new attendees, `None` if nobody is invited, or an error if an address is invalid.
+pub fn get_attendees(
+    matches: &ArgMatches,
+    current: Option<Vec<EventAttendee>>,
+) -> Result<Option<Vec<EventAttendee>>> {
+    let required = parse_emails(matches.get_many::<String>("invite").unwrap_or_default())?;
+    let optional = parse_emails(matches.get_many::<String>("optional").unwrap_or_default())?;
+    if required.is_empty() && optional.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(merge_attendees(
+        current.unwrap_or_default(),
+        &required,
+        &optional,
+    )))
+}
+
+/// Resolves the event selected by `event_selector_args`.
+///
+/// The `event` argument is first tried as an event ID. If no such event exists, the events
+/// whose title contains the given text are searched, either on the `--date` day or around
+/// today. When several events match, the user is asked to pick one, unless `interactive`
+/// is `false`, in which case an error is returned.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+/// * `tz` - The timezone used to interpret dates and display events.
+/// * `calendar_id` - The calendar to look in.
+/// * `matches` - The matches of a command using `event_selector_args`.
+/// * `interactive` - Whether the user may be prompted to choose between several matches.
+///
+/// ## Returns
+///
+/// * `Result<Event>` - The selected event, or an error if none (or more than one, non-interactively) matched.
+pub async fn resolve_event(
+    hub: &Hub,
+    tz: Tz,
+    calendar_id: &str,
+    matches: &ArgMatches,
+    interactive: bool,
+) -> Result<Event> {
+    let query = matches
+        .get_one::<String>("event")
+        .ok_or_else(|| anyhow!("No event given"))?;
+
+    if is_event_id(query) {
+        match hub.events().get(calendar_id, query).doit().await {
+            Ok((_, event)) if event.status.as_deref() != Some("cancelled") => return Ok(event),
+            // The text may still be a title, it is searched for below.
+            Ok(_) => {}
+            Err(e) if is_missing(&e) => {}
+            Err(e) => return Err(anyhow!(e).context("Failed to get event")),
+        }
+    }
+
+    let (time_min, time_max) = match matches.get_one::<String>("date") {
+        Some(date) => get_day_bounds(tz, get_naive_date_from_string(date)?),
+        None => {
+            let now = Utc::now();
+            (
+                now - Duration::days(SEARCH_DAYS_BEFORE),
+                now + Duration::days(SEARCH_DAYS_AFTER),
+            )
+        }
+    };
+    let needle = query.to_lowercase();
+    let mut candidates: Vec<Event> =
+        list_events(hub, calendar_id, time_min, time_max, Some(query), None)
+            .await?
+            .into_iter()
+            .filter(|event| {
+                event
+                    .summary
+                    .as_ref()
+                    .is_some_and(|summary| summary.to_lowercase().contains(&needle))
+            })
+            .collect();
+
+    match candidates.len() {
+        0 => bail!(GcalError::NotFound(format!(
+            "No event found matching '{}'",
+            query
+        ))),
+        1 => Ok(candidates.remove(0)),
+        _ if !interactive => bail!(
+            "{} events match '{}', use the event ID or --date to select one",
+            candidates.len(),
+            query
+        ),
+        _ => {
+            let options: Vec<String> = candidates
+                .iter()
+                .map(|event| format_event_line(event, tz))
+                .collect();
+            match prompt::pick("Select an event (number): ", &options)? {
+                Some(index) => Ok(candidates.remove(index)),
+                None => bail!("No event selected"),
+            }
+        }
+    }
+}
+
+/// Returns `true` if the text looks like a Google Calendar event ID.
+///
+/// Event IDs are 5 to 1024 lowercase base32hex characters (`0-9` and `a-v`), recurring
+/// instances additionally carry an `_<timestamp>` suffix. Words without a digit, such as
+/// `standup`, are taken as titles, since generated IDs practically always contain one.
+fn is_event_id(text: &str) -> bool {
+    let (id, instance) = match text.split_once('_') {
+        Some((id, instance)) => (id, Some(instance)),
+        None => (text, None),
+    };
+    let is_timestamp = |instance: &str| {
+        !instance.is_empty()
+            && instance
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == 'T' || c == 'Z')
+    };
+    (5..=1024).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='v').contains(&c))
+        && id.chars().any(|c| c.is_ascii_digit())
+        && instance.is_none_or(is_timestamp)
+}
+
+/// Returns `true` if the API reported that the requested event does not exist or was deleted.
+fn is_missing(error: &common::Error) -> bool {
+    matches!(
+        GcalError::from(error),
+        GcalError::Api {
+            status: Some(404 | 410),
+            ..
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_is_event_id() {
+        assert!(is_event_id("4k2jdr7vq0g9n1tq8n6l3t5s8c"));
+        assert!(is_event_id("4k2jdr7vq0g9n1tq8n6l3t5s8c_20240727T080000Z"));
+        assert!(!is_event_id("Retro & Demo"));
+        assert!(!is_event_id("Standup"));
+        assert!(!is_event_id("standup"));
+        assert!(!is_event_id("weekly1"));
+        assert!(!is_event_id("4k2jdr7vq0g9_"));
+        assert!(!is_event_id("abc"));
+    }
+
+    #[test]
+    fn test_is_missing() {
+        let api_error = |code: u16| {
+            common::Error::BadRequest(json!({
+                "error": { "code": code, "message": "Error" }
+            }))
+        };
+
+        assert!(is_missing(&api_error(404)));
+        assert!(is_missing(&api_error(410)));
+        assert!(!is_missing(&api_error(403)));
+        assert!(!is_missing(&common::Error::MissingAPIKey));
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
+use clap::{ArgMatches, Command};
+
+use super::{list_attended_events, print_with_template, template_arg};
+use crate::util::calendar::Hub;
+
+/// How far ahead the next event is looked for.
+const NEXT_EVENT_DAYS: i64 = 7;
+
+/// Builds the `next` subcommand.
+pub fn command() -> Command {
+    Command::new("next")
+        .about("Shows the next event, with a countdown and its Meet link")
+        .arg(template_arg("{start} {title} (in {countdown}) {link}"))
+}
+
+/// Prints the next timed event which starts after now and has not been declined.
+///
+/// Only the coming week is fetched. Nothing is printed when no event is found, so the
+/// output can be embedded as is in a status bar.
+pub async fn run(hub: &Hub, tz: Tz, calendar_ids: &[String], matches: &ArgMatches) -> Result<()> {
+    let now = Utc::now();
+    let mut items = list_attended_events(
+        hub,
+        tz,
+        calendar_ids,
+        now,
+        now + Duration::days(NEXT_EVENT_DAYS),
+    )
+    .await?;
+    items.retain(|(_, event)| {
+        event
+            .start
+            .as_ref()
+            .and_then(|start| start.date_time)
+            .is_some_and(|start| start > now)
+    });
+    items.truncate(1);
+    print_with_template(tz, &items, matches, now)
+}
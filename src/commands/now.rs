@@ -0,0 +1,25 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
+use clap::{ArgMatches, Command};
+
+use super::{list_attended_events, print_with_template, template_arg};
+use crate::util::calendar::Hub;
+
+/// Builds the `now` subcommand.
+pub fn command() -> Command {
+    Command::new("now")
+        .about("Shows the events taking place right now and when they end")
+        .arg(template_arg("{title} (until {end}, {remaining} left)"))
+}
+
+/// Prints the timed events in progress which have not been declined, one per line.
+///
+/// Nothing is printed when no event is in progress, so the output can be embedded as is in
+/// a status bar.
+pub async fn run(hub: &Hub, tz: Tz, calendar_ids: &[String], matches: &ArgMatches) -> Result<()> {
+    let now = Utc::now();
+    let items =
+        list_attended_events(hub, tz, calendar_ids, now, now + Duration::seconds(1)).await?;
+    print_with_template(tz, &items, matches, now)
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+
+use crate::util::calendar;
+use crate::util::config::{get_config_path, Config};
+use crate::util::error::GcalError;
+use crate::util::profile::{
+    create_profile, find_profile_dir, get_gcal_dir, list_profiles, remove_profile,
+};
+use crate::util::prompt;
+
+/// Builds the `profile` subcommand and its own subcommands.
+pub fn command() -> Command {
+    let profile_arg = || Arg::new("name").help("Profile name").required(true);
+    Command::new("profile")
+        .about("Manages the profiles used to sign in to several Google accounts")
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("Lists the profiles"))
+        .subcommand(
+            Command::new("add")
+                .about("Adds a profile and signs in to its Google account")
+                .arg(profile_arg())
+                .arg(
+                    Arg::new("secret")
+                        .help("Sets the secret.json of the Google Cloud project to sign in with")
+                        .long("secret"),
+                ),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Removes a profile with its secret and tokens")
+                .arg(profile_arg())
+                .arg(
+                    Arg::new("yes")
+                        .help("Removes without asking for confirmation")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("default")
+                .about("Sets the profile used when no --profile is given, or prints it")
+                .arg(Arg::new("name").help("Profile name")),
+        )
+}
+
+/// Runs the selected `profile` subcommand.
+///
+/// Each profile keeps its own secret and token store in `~/.gcal/profiles/<name>/`. Without any
+/// profile, the secret and token store directly in `~/.gcal` are used.
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let gcal_dir = get_gcal_dir()?;
+    let config_path = get_config_path()?;
+    let mut config = Config::load(&config_path)?;
+    let name = |matches: &ArgMatches| matches.get_one::<String>("name").cloned();
+    match matches.subcommand() {
+        Some(("add", matches)) => {
+            let name = name(matches).unwrap_or_default();
+            let secret = matches.get_one::<String>("secret").map(Path::new);
+            let dir = create_profile(&gcal_dir, &name, secret)?;
+            if let Err(e) = calendar::auth(&dir).await {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(e);
+            }
+            println!("Profile added, use it with gcal --profile {}", name);
+        }
+        Some(("remove", matches)) => {
+            let name = name(matches).unwrap_or_default();
+            let question = format!("Remove profile {} and sign out of its account?", name);
+            find_profile_dir(&gcal_dir, &name)?;
+            if !matches.get_flag("yes") && !prompt::confirm(&question)? {
+                println!("Cancelled.");
+                return Ok(());
+            }
+            remove_profile(&gcal_dir, &name)?;
+            if config.profile.as_ref() == Some(&name) {
+                config.unset("profile")?;
+                config.save(&config_path)?;
+            }
+            println!("Profile removed.");
+        }
+        Some(("default", matches)) => match name(matches) {
+            Some(name) => {
+                find_profile_dir(&gcal_dir, &name)?;
+                config.set("profile", &name)?;
+                config.save(&config_path)?;
+                println!("Default profile set to {}", name);
+            }
+            None => match &config.profile {
+                Some(profile) => println!("{}", profile),
+                None => bail!(GcalError::NotFound("No default profile is set".to_string())),
+            },
+        },
+        _ => {
+            let profiles = list_profiles(&gcal_dir)?;
+            if profiles.is_empty() {
+                println!("No profiles yet, add one with gcal profile add <name>");
+                return Ok(());
+            }
+            println!("{}", render_profile_table(&gcal_dir, &profiles, &config)?);
+        }
+    }
+    Ok(())
+}
+
+/// Renders the profiles as a table showing the default one and whether they are signed in.
+fn render_profile_table(gcal_dir: &Path, profiles: &[String], config: &Config) -> Result<Table> {
+    let mut table = Table::new();
+    table
+        .set_header(
+            ["Name", "Default", "Signed in"]
+                .map(|title| Cell::new(title).add_attribute(Attribute::Bold)),
+        )
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    for profile in profiles {
+        let dir = find_profile_dir(gcal_dir, profile)?;
+        table.add_row(vec![
+            profile.as_str(),
+            yes_no(config.profile.as_ref() == Some(profile)),
+            yes_no(dir.join("store.json").exists()),
+        ]);
+    }
+    Ok(table)
+}
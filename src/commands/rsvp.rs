@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Context, Result};
+use chrono_tz::Tz;
+use clap::{Arg, ArgMatches, Command};
+use google_calendar3::api::Event;
+
+use super::{event_selector_args, resolve_event};
+use crate::util::attendee::{get_response_status, set_own_response, RSVP_RESPONSES};
+use crate::util::calendar::Hub;
+
+/// Builds the `rsvp` subcommand.
+pub fn command() -> Command {
+    Command::new("rsvp")
+        .about("Accepts, declines or tentatively accepts an invitation")
+        .args(event_selector_args())
+        .arg(
+            Arg::new("response")
+                .help("Sets the answer to the invitation")
+                .value_parser(RSVP_RESPONSES)
+                .required(true),
+        )
+        .arg(
+            Arg::new("comment")
+                .help("Adds a comment to the answer")
+                .long("comment")
+                .short('m'),
+        )
+}
+
+/// Answers the invitation to the selected event on behalf of the current user.
+///
+/// The attendees of an event can only be patched as a whole, so the full list is sent back
+/// with the response of the current user changed.
+pub async fn run(hub: &Hub, tz: Tz, calendar_id: &str, matches: &ArgMatches) -> Result<()> {
+    let response = matches
+        .get_one::<String>("response")
+        .map_or("yes", String::as_str);
+    let response_status = get_response_status(response);
+
+    let event = resolve_event(hub, tz, calendar_id, matches, true).await?;
+    let event_id = event
+        .id
+        .as_deref()
+        .ok_or_else(|| anyhow!("The event has no ID"))?;
+    let attendees = set_own_response(
+        event.attendees.clone().unwrap_or_default(),
+        response_status,
+        matches.get_one::<String>("comment").map(String::as_str),
+    )?;
+    let patch = Event {
+        attendees: Some(attendees),
+        ..Default::default()
+    };
+
+    hub.events()
+        .patch(patch, calendar_id, event_id)
+        .send_updates("all")
+        .doit()
+        .await
+        .with_context(|| format!("Failed to answer invitation {}", event_id))?;
+    let answer = match response_status {
+        "accepted" => "accepted",
+        "declined" => "declined",
+        _ => "tentatively accepted",
+    };
+    println!(
+        "Invitation to '{}' {}.",
+        event.summary.as_deref().unwrap_or_default(),
+        answer
+    );
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use anyhow::{bail, Result};
+use chrono::{Duration, Local};
+use chrono_tz::Tz;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use super::views::render_agenda_view;
+use crate::util::attendee::has_attendee;
+use crate::util::calendar::{list_events_of_calendars, Hub};
+use crate::util::date::{get_day_bounds, get_naive_date_from_string};
+use crate::util::error::GcalError;
+
+/// The number of days searched when `--from` or `--to` is missing.
+const DEFAULT_SEARCH_DAYS: i64 = 365;
+
+/// Builds the `search` subcommand.
+pub fn command() -> Command {
+    Command::new("search")
+        .about("Searches events by text in their title, description, location or attendees")
+        .arg(
+            Arg::new("query")
+                .help("Sets the text to search for")
+                .required(true),
+        )
+        .arg(
+            Arg::new("from")
+                .help("Sets the first day searched (YYYY-MM-DD or MM-DD), today by default")
+                .long("from"),
+        )
+        .arg(
+            Arg::new("to")
+                .help("Sets the last day searched (YYYY-MM-DD or MM-DD), a year after --from by default")
+                .long("to"),
+        )
+        .arg(
+            Arg::new("attendee")
+                .help("Keeps only events with an attendee matching this email, domain or name")
+                .long("attendee")
+                .short('a')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("location")
+                .help("Keeps only events whose location contains this text")
+                .long("location")
+                .short('l'),
+        )
+        .arg(
+            Arg::new("limit")
+                .help("Shows at most the given number of events")
+                .long("limit")
+                .short('n')
+                .value_parser(value_parser!(usize)),
+        )
+}
+
+/// Prints the events of every given calendar matching the query, in the agenda layout.
+///
+/// The query is matched by the API; `--attendee` and `--location` narrow the results down
+/// further. The IDs shown can be passed to `show`, `edit`, `delete` and `rsvp`.
+pub async fn run(hub: &Hub, tz: Tz, calendar_ids: &[String], matches: &ArgMatches) -> Result<()> {
+    let query = matches
+        .get_one::<String>("query")
+        .map_or("", String::as_str);
+    let parse_date = |id: &str| {
+        matches
+            .get_one::<String>(id)
+            .map(|date| get_naive_date_from_string(date))
+            .transpose()
+    };
+    let (from, to) = match (parse_date("from")?, parse_date("to")?) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(from), None) => (from, from + Duration::days(DEFAULT_SEARCH_DAYS)),
+        (None, to) => {
+            let today = Local::now().date_naive();
+            (
+                today,
+                to.unwrap_or(today + Duration::days(DEFAULT_SEARCH_DAYS)),
+            )
+        }
+    };
+    if to < from {
+        bail!(GcalError::Parse(
+            "--to must not be before --from".to_string()
+        ));
+    }
+    let (time_min, _) = get_day_bounds(tz, from);
+    let (_, time_max) = get_day_bounds(tz, to);
+
+    let attendees: Vec<&String> = matches
+        .get_many::<String>("attendee")
+        .unwrap_or_default()
+        .collect();
+    let location = matches
+        .get_one::<String>("location")
+        .map(|location| location.to_lowercase());
+    let limit = matches.get_one::<usize>("limit").copied();
+    let has_filters = !attendees.is_empty() || location.is_some();
+
+    let mut items = list_events_of_calendars(
+        hub,
+        tz,
+        calendar_ids,
+        time_min,
+        time_max,
+        Some(query),
+        limit.filter(|_| !has_filters),
+    )
+    .await?;
+    items.retain(|(_, event)| {
+        attendees
+            .iter()
+            .all(|attendee| has_attendee(event, attendee))
+            && location.as_ref().is_none_or(|location| {
+                event
+                    .location
+                    .as_ref()
+                    .is_some_and(|value| value.to_lowercase().contains(location))
+            })
+    });
+    items.truncate(limit.unwrap_or(items.len()));
+    if items.is_empty() {
+        bail!(GcalError::NotFound(format!(
+            "No event matching '{}' between {} and {}",
+            query, from, to
+        )));
+    }
+
+    println!("{}", render_agenda_view(tz, &items));
+    Ok(())
+}
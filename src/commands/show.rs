@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use chrono_tz::Tz;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
+use google_calendar3::api::Event;
+
+use super::{event_selector_args, resolve_event};
+use crate::util::calendar::Hub;
+use crate::util::event::{format_event_time, get_conference_link};
+
+/// Builds the `show` subcommand.
+pub fn command() -> Command {
+    Command::new("show")
+        .about("Shows the details of an event")
+        .args(event_selector_args())
+        .arg(
+            Arg::new("raw")
+                .help("Prints the full event as returned by the API, in JSON")
+                .long("raw")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Prints the selected event as a card, or as raw JSON with `--raw`.
+pub async fn run(hub: &Hub, tz: Tz, calendar_id: &str, matches: &ArgMatches) -> Result<()> {
+    let event = resolve_event(hub, tz, calendar_id, matches, true).await?;
+    if matches.get_flag("raw") {
+        let json = serde_json::to_string_pretty(&event).context("Failed to serialize event")?;
+        println!("{}", json);
+    } else {
+        println!("{}", render_event_card(&event, tz));
+    }
+    Ok(())
+}
+
+/// Renders every human-relevant field of an event as a two-column table.
+///
+/// Fields which are not set on the event are left out of the card.
+pub fn render_event_card(event: &Event, tz: Tz) -> Table {
+    let mut rows: Vec<(&str, String)> = vec![("When", format_event_time(event, tz))];
+    if let Some(location) = &event.location {
+        rows.push(("Location", location.to_string()));
+    }
+    if let Some(conference) = format_conference(event) {
+        rows.push(("Conference", conference));
+    }
+    if let Some(organizer) = &event.organizer {
+        let organizer = organizer.display_name.as_ref().or(organizer.email.as_ref());
+        if let Some(organizer) = organizer {
+            rows.push(("Organizer", organizer.to_string()));
+        }
+    }
+    if let Some(attendees) = event.attendees.as_ref().filter(|a| !a.is_empty()) {
+        let attendees = attendees
+            .iter()
+            .map(|attendee| {
+                format!(
+                    "{} ({}{})",
+                    attendee
+                        .display_name
+                        .as_ref()
+                        .or(attendee.email.as_ref())
+                        .map_or("unknown", String::as_str),
+                    attendee.response_status.as_deref().unwrap_or("needsAction"),
+                    if attendee.optional == Some(true) {
+                        ", optional"
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        rows.push(("Attendees", attendees));
+    }
+    if let Some(recurrence) = &event.recurrence {
+        rows.push(("Recurrence", recurrence.join("\n")));
+    }
+    if let Some(reminders) = format_reminders(event) {
+        rows.push(("Reminders", reminders));
+    }
+    if let Some(description) = &event.description {
+        rows.push(("Description", description.to_string()));
+    }
+    if let Some(status) = &event.status {
+        rows.push(("Status", status.to_string()));
+    }
+    if let Some(link) = &event.html_link {
+        rows.push(("Link", link.to_string()));
+    }
+    if let Some(id) = &event.id {
+        rows.push(("ID", id.to_string()));
+    }
+
+    let mut table = Table::new();
+    table
+        .set_header(vec![
+            Cell::new(""),
+            Cell::new(event.summary.as_deref().unwrap_or("(no title)"))
+                .fg(Color::DarkGreen)
+                .add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for (label, value) in rows {
+        table.add_row(vec![
+            Cell::new(label).add_attribute(Attribute::Bold),
+            Cell::new(value),
+        ]);
+    }
+    table
+}
+
+/// Lists every entry point of the conference with its type, e.g. `Phone: +1 555-0100 (PIN 123)`,
+/// or only the Meet link if the event has no conference data.
+fn format_conference(event: &Event) -> Option<String> {
+    let entry_points = event
+        .conference_data
+        .as_ref()
+        .and_then(|conference| conference.entry_points.as_ref())
+        .filter(|entry_points| !entry_points.is_empty());
+    let Some(entry_points) = entry_points else {
+        return get_conference_link(event);
+    };
+    let lines: Vec<String> = entry_points
+        .iter()
+        .map(|entry_point| {
+            let kind = match entry_point.entry_point_type.as_deref() {
+                Some("video") => "Video",
+                Some("phone") => "Phone",
+                Some("sip") => "SIP",
+                _ => "More",
+            };
+            let address = entry_point
+                .label
+                .as_ref()
+                .or(entry_point.uri.as_ref())
+                .map_or("", String::as_str);
+            let codes: Vec<String> = [
+                ("PIN", &entry_point.pin),
+                ("passcode", &entry_point.passcode),
+                ("password", &entry_point.password),
+                ("access code", &entry_point.access_code),
+            ]
+            .into_iter()
+            .filter_map(|(name, code)| Some(format!("{} {}", name, code.as_ref()?)))
+            .collect();
+            if codes.is_empty() {
+                format!("{}: {}", kind, address)
+            } else {
+                format!("{}: {} ({})", kind, address, codes.join(", "))
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+fn format_reminders(event: &Event) -> Option<String> {
+    let reminders = event.reminders.as_ref()?;
+    if reminders.use_default == Some(true) {
+        return Some("Calendar default".to_string());
+    }
+    let overrides = reminders.overrides.as_ref()?;
+    Some(
+        overrides
+            .iter()
+            .map(|reminder| {
+                format!(
+                    "{} minutes before ({})",
+                    reminder.minutes.unwrap_or_default(),
+                    reminder.method.as_deref().unwrap_or("popup")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use google_calendar3::api::{ConferenceData, EntryPoint, EventAttendee, EventDateTime};
+
+    use super::*;
+
+    #[test]
+    fn test_render_event_card() {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+        let event = Event {
+            summary: Some("Retro".to_string()),
+            start: Some(EventDateTime {
+                date: chrono::NaiveDate::from_ymd_opt(2024, 7, 27),
+                ..Default::default()
+            }),
+            location: Some("Room 42".to_string()),
+            hangout_link: Some("https://meet.google.com/abc-defg-hij".to_string()),
+            attendees: Some(vec![EventAttendee {
+                email: Some("alice@example.com".to_string()),
+                response_status: Some("accepted".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let card = render_event_card(&event, tz).to_string();
+
+        assert!(card.contains("Retro"));
+        assert!(card.contains("2024-07-27 (all day)"));
+        assert!(card.contains("Room 42"));
+        assert!(card.contains("https://meet.google.com/abc-defg-hij"));
+        assert!(card.contains("alice@example.com (accepted)"));
+        assert!(!card.contains("Description"));
+    }
+
+    #[test]
+    fn test_render_conference_entry_points() {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+        let entry_point =
+            |kind: &str, uri: &str, label: Option<&str>, pin: Option<&str>| EntryPoint {
+                entry_point_type: Some(kind.to_string()),
+                uri: Some(uri.to_string()),
+                label: label.map(str::to_string),
+                pin: pin.map(str::to_string),
+                ..Default::default()
+            };
+        let event = Event {
+            summary: Some("Retro".to_string()),
+            hangout_link: Some("https://meet.google.com/abc-defg-hij".to_string()),
+            conference_data: Some(ConferenceData {
+                entry_points: Some(vec![
+                    entry_point("video", "https://meet.google.com/abc-defg-hij", None, None),
+                    entry_point(
+                        "phone",
+                        "tel:+1-555-0100",
+                        Some("+1 555-0100"),
+                        Some("123456"),
+                    ),
+                    entry_point("sip", "sip:123@meet.example.com", None, None),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let card = render_event_card(&event, tz).to_string();
+
+        assert!(card.contains("Video: https://meet.google.com/abc-defg-hij"));
+        assert!(card.contains("Phone: +1 555-0100 (PIN 123456)"));
+        assert!(card.contains("SIP: sip:123@meet.example.com"));
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::{BTreeMap, HashMap};
+use std::{collections::hash_map::Entry, fmt::Write};
+
+use chrono::{Datelike, Duration, Month, NaiveDate, Timelike, Weekday};
+use chrono_tz::Tz;
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
+use google_calendar3::api::Event;
+
+use crate::util::date::days_in_english;
+use crate::util::event::get_all_day_dates;
+
+/// The layouts available to render a set of events.
+pub const VIEWS: [&str; 4] = ["week", "day", "month", "agenda"];
+
+/// The number of event titles shown in a single day of the month view.
+const MONTH_VIEW_TITLES_PER_DAY: usize = 3;
+
+/// The events of a date range, bucketed by the local day they happen on.
+struct EventDates<'a> {
+    /// Timed events starting and ending on the same day, ordered by start time.
+    timed: HashMap<NaiveDate, Vec<&'a Event>>,
+    /// All-day and multi-day events, listed on every day they cover.
+    all_day: HashMap<NaiveDate, Vec<&'a Event>>,
+}
+
+impl<'a> EventDates<'a> {
+    fn new(tz: Tz, events: &'a [(String, Event)]) -> EventDates<'a> {
+        let mut timed: HashMap<_, Vec<_>> = HashMap::new();
+        let mut all_day: HashMap<_, Vec<_>> = HashMap::new();
+        for (_, event) in events {
+            if let Some(dates) = get_all_day_dates(event, tz) {
+                for date in dates {
+                    all_day.entry(date).or_default().push(event);
+                }
+                continue;
+            }
+            let Some(event_start) = event.start.as_ref().and_then(|start| start.date_time) else {
+                continue;
+            };
+            match timed.entry(event_start.with_timezone(&tz).date_naive()) {
+                Entry::Vacant(e) => {
+                    e.insert(vec![event]);
+                }
+                Entry::Occupied(mut e) => {
+                    e.get_mut().push(event);
+                }
+            }
+        }
+        EventDates { timed, all_day }
+    }
+
+    fn timed(&self, date: &NaiveDate) -> &[&'a Event] {
+        self.timed.get(date).map_or(&[], Vec::as_slice)
+    }
+
+    fn all_day(&self, date: &NaiveDate) -> &[&'a Event] {
+        self.all_day.get(date).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Renders the events with one column per day, at most seven days per table.
+///
+/// The events of each day are split into a before-noon and an after-noon row. All-day and
+/// multi-day events are shown in a banner row above them, repeated on every day they cover.
+pub fn render_week_view(
+    tz: Tz,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    events: &[(String, Event)],
+) -> Vec<Table> {
+    let event_dates = EventDates::new(tz, events);
+    get_dates(start_date, end_date)
+        .chunks(7)
+        .map(|week| render_week_table(tz, week, &event_dates))
+        .collect()
+}
+
+fn render_week_table(tz: Tz, dates: &[NaiveDate], event_dates: &EventDates) -> Table {
+    let mut table: Table = Table::new();
+    let mut all_day_row: Vec<Cell> = vec![];
+    let mut row_before_12: Vec<String> = vec![];
+    let mut row_after_12: Vec<String> = vec![];
+    let mut header: Vec<Cell> = vec![];
+    for next_date in dates {
+        header.push(get_day_header(next_date));
+
+        let all_day_value: Vec<&str> = event_dates
+            .all_day(next_date)
+            .iter()
+            .map(|event| get_summary(event))
+            .collect();
+        all_day_row.push(Cell::new(all_day_value.join("\n")).fg(Color::DarkYellow));
+
+        let mut row_value_before_12: String = "".to_string();
+        let mut row_value_after_12: String = "".to_string();
+        for next_event in event_dates.timed(next_date) {
+            let Some((event_start, event_end)) = get_local_times(tz, next_event) else {
+                continue;
+            };
+            let formatted_event = format!(
+                "{:02}:{:02} - {:02}:{:02}: {}\n\n",
+                event_start.hour(),
+                event_start.minute(),
+                event_end.hour(),
+                event_end.minute(),
+                get_summary(next_event)
+            );
+
+            if event_start.hour() < 12 {
+                write!(row_value_before_12, "{}", formatted_event).unwrap();
+            } else {
+                write!(row_value_after_12, "{}", formatted_event).unwrap();
+            }
+        }
+        row_before_12.push(row_value_before_12);
+        row_after_12.push(row_value_after_12);
+    }
+
+    table.set_header(header);
+    if all_day_row.iter().any(|cell| !cell.content().is_empty()) {
+        table.add_row(all_day_row);
+    }
+    table
+        .add_row(row_before_12)
+        .add_row(row_after_12)
+        .set_content_arrangement(ContentArrangement::DynamicFullWidth);
+    table
+}
+
+/// Renders one hour-by-hour timeline per day.
+///
+/// The timeline covers at least 08:00 to 18:00 and is extended to include every event of
+/// the day. Events spanning several hours are marked as ongoing in the following hours.
+pub fn render_day_view(
+    tz: Tz,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    events: &[(String, Event)],
+) -> Vec<Table> {
+    let event_dates = EventDates::new(tz, events);
+    get_dates(start_date, end_date)
+        .iter()
+        .map(|date| {
+            let timed: Vec<_> = event_dates
+                .timed(date)
+                .iter()
+                .filter_map(|event| Some((get_local_times(tz, event)?, get_summary(event))))
+                .collect();
+            let first_hour = timed
+                .iter()
+                .map(|((start, _), _)| start.hour())
+                .fold(8, u32::min);
+            let last_hour = timed
+                .iter()
+                .map(|((_, end), _)| {
+                    if end.date_naive() > *date {
+                        24
+                    } else {
+                        end.hour() + u32::from(end.minute() > 0)
+                    }
+                })
+                .fold(18, u32::max)
+                .min(24);
+
+            let mut table = Table::new();
+            table
+                .set_header(vec![Cell::new(""), get_day_header(date)])
+                .set_content_arrangement(ContentArrangement::Dynamic);
+            let all_day: Vec<&str> = event_dates
+                .all_day(date)
+                .iter()
+                .map(|event| get_summary(event))
+                .collect();
+            if !all_day.is_empty() {
+                table.add_row(vec![
+                    Cell::new("All day"),
+                    Cell::new(all_day.join("\n")).fg(Color::DarkYellow),
+                ]);
+            }
+            for hour in first_hour..last_hour {
+                let mut lines: Vec<String> = vec![];
+                for ((start, end), summary) in &timed {
+                    let hour_start = start.with_hour(hour).and_then(|h| h.with_minute(0));
+                    if start.hour() == hour {
+                        lines.push(format!(
+                            "{} - {} {}",
+                            start.format("%H:%M"),
+                            end.format("%H:%M"),
+                            summary
+                        ));
+                    } else if start.hour() < hour && hour_start.is_some_and(|h| *end > h) {
+                        lines.push(format!("│ {}", summary));
+                    }
+                }
+                table.add_row(vec![
+                    Cell::new(format!("{:02}:00", hour)).fg(Color::DarkGrey),
+                    Cell::new(lines.join("\n")),
+                ]);
+            }
+            table
+        })
+        .collect()
+}
+
+/// Renders the events as a calendar grid with one row per week.
+///
+/// Every day shows its number, its first few event titles and how many more events it has.
+/// Days outside of the range are dimmed, and the weeks start on `week_start`.
+pub fn render_month_view(
+    tz: Tz,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    week_start: Weekday,
+    events: &[(String, Event)],
+) -> Table {
+    let event_dates = EventDates::new(tz, events);
+    let grid_start =
+        start_date - Duration::days(start_date.weekday().days_since(week_start) as i64);
+    let last_date = end_date - Duration::days(1);
+    let grid_end =
+        last_date + Duration::days(7 - last_date.weekday().days_since(week_start) as i64);
+
+    let mut days = days_in_english();
+    days.rotate_left(week_start.num_days_from_monday() as usize);
+    let mut table = Table::new();
+    table
+        .set_header(days.iter().map(|day| {
+            let cell = Cell::new(day);
+            if !matches!(*day, "Saturday" | "Sunday") {
+                cell.fg(Color::DarkGreen).add_attribute(Attribute::Bold)
+            } else {
+                cell.fg(Color::DarkBlue)
+            }
+        }))
+        .set_content_arrangement(ContentArrangement::DynamicFullWidth);
+    for week in get_dates(grid_start, grid_end).chunks(7) {
+        table.add_row(week.iter().map(|date| {
+            if *date < start_date || *date >= end_date {
+                return Cell::new(date.day()).fg(Color::DarkGrey);
+            }
+            let titles: Vec<String> = event_dates
+                .all_day(date)
+                .iter()
+                .map(|event| get_summary(event).to_string())
+                .chain(event_dates.timed(date).iter().map(|event| {
+                    match get_local_times(tz, event) {
+                        Some((start, _)) => {
+                            format!("{} {}", start.format("%H:%M"), get_summary(event))
+                        }
+                        None => get_summary(event).to_string(),
+                    }
+                }))
+                .collect();
+            let mut value = format!("{}", date.day());
+            for title in titles.iter().take(MONTH_VIEW_TITLES_PER_DAY) {
+                write!(value, "\n{}", title).unwrap();
+            }
+            if titles.len() > MONTH_VIEW_TITLES_PER_DAY {
+                write!(
+                    value,
+                    "\n+{} more",
+                    titles.len() - MONTH_VIEW_TITLES_PER_DAY
+                )
+                .unwrap();
+            }
+            Cell::new(value)
+        }));
+    }
+    table
+}
+
+/// Renders the events as a chronological list grouped by date, with their IDs.
+///
+/// A calendar column is added when the events come from more than one calendar.
+pub fn render_agenda_view(tz: Tz, events: &[(String, Event)]) -> Table {
+    let mut dates: BTreeMap<NaiveDate, Vec<(&str, &Event, String)>> = BTreeMap::new();
+    for (calendar_id, event) in events {
+        if let Some(days) = get_all_day_dates(event, tz) {
+            for day in days {
+                dates
+                    .entry(day)
+                    .or_default()
+                    .push((calendar_id, event, "all day".to_string()));
+            }
+        } else if let Some((start, end)) = get_local_times(tz, event) {
+            let time = format!("{} - {}", start.format("%H:%M"), end.format("%H:%M"));
+            dates
+                .entry(start.date_naive())
+                .or_default()
+                .push((calendar_id, event, time));
+        }
+    }
+    let show_calendar = events
+        .iter()
+        .any(|(calendar_id, _)| Some(calendar_id) != events.first().map(|(id, _)| id));
+
+    let mut table = Table::new();
+    let mut header = vec!["Date", "Time", "Event"];
+    if show_calendar {
+        header.push("Calendar");
+    }
+    header.push("ID");
+    table
+        .set_header(
+            header
+                .into_iter()
+                .map(|title| Cell::new(title).add_attribute(Attribute::Bold)),
+        )
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for (date, date_events) in dates {
+        for (i, (calendar_id, event, time)) in date_events.into_iter().enumerate() {
+            let date_cell = if i == 0 {
+                get_day_header(&date)
+            } else {
+                Cell::new("")
+            };
+            let mut row = vec![date_cell, Cell::new(time), Cell::new(get_summary(event))];
+            if show_calendar {
+                row.push(Cell::new(calendar_id));
+            }
+            row.push(Cell::new(event.id.as_deref().unwrap_or_default()).fg(Color::DarkGrey));
+            table.add_row(row);
+        }
+    }
+    table
+}
+
+fn get_dates(start_date: NaiveDate, end_date: NaiveDate) -> Vec<NaiveDate> {
+    start_date
+        .iter_days()
+        .take_while(|date| *date < end_date)
+        .collect()
+}
+
+/// Returns a `Weekday - day Month` header cell, highlighting working days.
+fn get_day_header(date: &NaiveDate) -> Cell {
+    let weekday = date.weekday().num_days_from_monday();
+    let header_value = format!(
+        "{} - {} {:?}",
+        days_in_english()[weekday as usize],
+        date.day(),
+        Month::try_from(u8::try_from(date.month()).unwrap())
+            .ok()
+            .unwrap()
+    );
+    if weekday < 5 {
+        Cell::new(header_value)
+            .fg(Color::DarkGreen)
+            .add_attribute(Attribute::Bold)
+    } else {
+        Cell::new(header_value).fg(Color::DarkBlue)
+    }
+}
+
+fn get_summary(event: &Event) -> &str {
+    event.summary.as_deref().unwrap_or("(no title)")
+}
+
+/// Returns the start and end of a timed event in the given timezone.
+fn get_local_times(tz: Tz, event: &Event) -> Option<(chrono::DateTime<Tz>, chrono::DateTime<Tz>)> {
+    let start = event.start.as_ref()?.date_time?.with_timezone(&tz);
+    let end = event
+        .end
+        .as_ref()
+        .and_then(|end| end.date_time)
+        .map_or(start, |end| end.with_timezone(&tz));
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use google_calendar3::api::EventDateTime;
+
+    use super::*;
+
+    fn events() -> Vec<(String, Event)> {
+        let timed = |id: &str, summary: &str, day, hour| Event {
+            id: Some(id.to_string()),
+            summary: Some(summary.to_string()),
+            start: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, day, hour, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            end: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, day, hour + 2, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let offsite = Event {
+            id: Some("offsite".to_string()),
+            summary: Some("Offsite".to_string()),
+            start: Some(EventDateTime {
+                date: NaiveDate::from_ymd_opt(2024, 7, 23),
+                ..Default::default()
+            }),
+            end: Some(EventDateTime {
+                date: NaiveDate::from_ymd_opt(2024, 7, 25),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        vec![
+            ("primary".to_string(), timed("standup", "Standup", 22, 9)),
+            ("primary".to_string(), offsite),
+            ("team".to_string(), timed("retro", "Retro", 24, 14)),
+        ]
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 7, day).unwrap()
+    }
+
+    #[test]
+    fn test_render_week_view() {
+        let tz: Tz = "UTC".parse().unwrap();
+
+        let mut tables = render_week_view(tz, date(22), date(31), &events());
+
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].column_count(), 7);
+        assert_eq!(tables[1].column_count(), 2);
+        let week = tables[0].to_string();
+        assert!(week.contains("09:00 - 11:00: Standup"));
+        assert!(week.contains("14:00 - 16:00: Retro"));
+        assert_eq!(week.matches("Offsite").count(), 2);
+    }
+
+    #[test]
+    fn test_render_day_view() {
+        let tz: Tz = "UTC".parse().unwrap();
+
+        let tables = render_day_view(tz, date(24), date(25), &events());
+
+        assert_eq!(tables.len(), 1);
+        let day = tables[0].to_string();
+        assert!(day.contains("All day"));
+        assert!(day.contains("14:00 - 16:00 Retro"));
+        assert!(day.contains("│ Retro"));
+        assert!(day.contains("08:00"));
+        assert!(!day.contains("18:00"));
+    }
+
+    #[test]
+    fn test_render_day_view_until_midnight() {
+        let tz: Tz = "UTC".parse().unwrap();
+        let release = Event {
+            summary: Some("Release".to_string()),
+            start: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 24, 23, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            end: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 25, 0, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let tables = render_day_view(tz, date(24), date(25), &[("primary".to_string(), release)]);
+
+        let day = tables[0].to_string();
+        assert!(day.contains("23:00 - 00:00 Release"), "{}", day);
+        assert!(!day.contains("All day"));
+        assert!(!day.contains("24:00"));
+    }
+
+    #[test]
+    fn test_render_month_view() {
+        let tz: Tz = "UTC".parse().unwrap();
+
+        let table = render_month_view(
+            tz,
+            date(1),
+            date(1) + Duration::days(31),
+            Weekday::Mon,
+            &events(),
+        );
+
+        assert_eq!(table.row_count(), 5);
+        let month = table.to_string();
+        assert!(month.contains("09:00 Standup"));
+        assert_eq!(month.matches("Offsite").count(), 2);
+        assert!(month.find("Monday") < month.find("Sunday"));
+
+        let month = render_month_view(
+            tz,
+            date(1),
+            date(1) + Duration::days(31),
+            Weekday::Sun,
+            &events(),
+        )
+        .to_string();
+        assert!(month.find("Sunday") < month.find("Monday"));
+    }
+
+    #[test]
+    fn test_render_agenda_view() {
+        let tz: Tz = "UTC".parse().unwrap();
+
+        let agenda = render_agenda_view(tz, &events()).to_string();
+
+        assert!(agenda.contains("Calendar"));
+        assert!(agenda.contains("standup"));
+        assert!(agenda.contains("all day"));
+        let standup = agenda.find("Standup").unwrap();
+        let retro = agenda.find("Retro").unwrap();
+        assert!(standup < retro);
+    }
+}
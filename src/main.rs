@@ -1,17 +1,14 @@
+mod commands;
 mod util;
 
-use std::collections::HashMap;
-use std::{collections::hash_map::Entry, fmt::Write};
+use std::process;
 
-use chrono::{Datelike, Duration, Month, Timelike, TimeZone};
 use chrono_tz::Tz;
-use clap::{Arg, ArgAction, Command};
-use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
-use google_calendar3::api::{ConferenceData, ConferenceSolutionKey, CreateConferenceRequest};
-use google_calendar3::api::{Event, EventDateTime};
-use util::calendar::{self, get_default_timezone};
-use util::date::{days_in_english, get_date_from_string, get_start_of_the_week};
-use uuid::Uuid;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use util::calendar::{self, get_default_timezone, Account};
+use util::config::{get_config_path, Config};
+use util::error::{format_error, get_exit_code, GcalError};
+use util::profile::{find_profile_dir, get_gcal_dir};
 
 #[tokio::main]
 
@@ -27,206 +24,197 @@ async fn main() {
                 .required(false),
         )
         .arg(Arg::new("date").help("Sets the event date").required(false))
+        .args(commands::add::options())
+        .subcommand(commands::add::command())
         .arg(
-            Arg::new("conference")
-                .help("Indicates that this event will be a conference Google Meet")
-                .long("conference")
-                .short('c')
-                .action(ArgAction::SetTrue)
-                .required(false)
-                .requires("title"),
+            Arg::new("calendar")
+                .help("Calendar ID or name to use instead of the primary calendar")
+                .long("calendar")
+                .short('C')
+                .global(true)
+                .action(ArgAction::Append),
         )
-        .subcommand(
-            Command::new("add")
-                .about("Adds a new event to Google Calendar")
-                .arg(
-                    Arg::new("title")
-                        .help("Sets the event title")
-                        .required(true),
-                )
-                .arg(Arg::new("date").help("Sets the event date").required(true)),
+        .arg(
+            Arg::new("profile")
+                .help("Profile of the Google account to use, can be given several times for list")
+                .long("profile")
+                .short('P')
+                .global(true)
+                .action(ArgAction::Append),
         )
-        .subcommand(Command::new("list").about("Lists all events in Google Calendar"))
+        .subcommand(commands::list::command())
+        .subcommand(commands::delete::command())
+        .subcommand(commands::edit::command())
+        .subcommand(commands::show::command())
+        .subcommand(commands::rsvp::command())
+        .subcommand(commands::search::command())
+        .subcommand(commands::next::command())
+        .subcommand(commands::now::command())
+        .subcommand(commands::free::command())
+        .subcommand(commands::export::command())
+        .subcommand(commands::import::command())
+        .subcommand(commands::calendars::command())
+        .subcommand(commands::config::command())
+        .subcommand(commands::profile::command())
         .get_matches();
 
-    let hub = match calendar::auth().await {
-        Ok(hub) => hub,
-        Err(e) => {
-            eprintln!("Error during authentication - {}", e);
-            return;
-        }
-    };
-
-    let tz: Tz = get_default_timezone(&hub).await.unwrap();
+    if let Err((context, e)) = run(&matches).await {
+        eprintln!("{}: {}", context, format_error(&e));
+        process::exit(get_exit_code(&e));
+    }
+}
 
+/// Runs the selected command, returning the failed step together with its error.
+async fn run(matches: &ArgMatches) -> Result<(), (&'static str, anyhow::Error)> {
     match matches.subcommand() {
-        Some(("list", _)) => {
-            let start_of_the_week = get_start_of_the_week();
-            let start_of_the_week_utc = start_of_the_week.with_hour(0).unwrap().to_utc();
-
-            let events = hub
-                .events()
-                .list("primary")
-                .time_min(start_of_the_week_utc)
-                .time_max(start_of_the_week_utc + Duration::days(7))
-                .single_events(true)
-                .doit()
-                .await;
-            match events {
-                Ok((_, events)) => {
-                    let mut table: Table = Table::new();
-                    let mut event_dates: HashMap<_, Vec<_>> = HashMap::new();
-
-                    if let Some(items) = events.items {
-                        for event in items {
-                            if event.start.as_ref().is_none()
-                                || event.start.as_ref().unwrap().date_time.is_none()
-                            {
-                                continue;
-                            }
-                            let event_start =
-                                event.start.as_ref().unwrap().date_time.unwrap().date_naive();
-                            match event_dates.entry(event_start) {
-                                Entry::Vacant(e) => {
-                                    e.insert(vec![event]);
-                                }
-                                Entry::Occupied(mut e) => {
-                                    e.get_mut().push(event);
-                                }
-                            }
-                        }
-                    }
-
-                    let mut row_before_12: Vec<String> = vec![];
-                    let mut row_after_12: Vec<String> = vec![];
-                    let mut header: Vec<Cell> = vec![];
-                    for (i, v) in days_in_english().iter().enumerate() {
-                        let i = i as i64;
-                        let next_date = start_of_the_week + Duration::days(i);
-                        let header_value = format!(
-                            "{} - {} {:?}",
-                            v,
-                            next_date.day(),
-                            Month::try_from(u8::try_from(next_date.month()).unwrap())
-                                .ok()
-                                .unwrap()
-                        );
-                        if i < 5 {
-                            header.push(
-                                Cell::new(header_value)
-                                    .fg(Color::DarkGreen)
-                                    .add_attribute(Attribute::Bold),
-                            );
-                        } else {
-                            header.push(Cell::new(header_value).fg(Color::DarkBlue));
-                        }
-
-                        let mut row_value_before_12: String = "".to_string();
-                        let mut row_value_after_12: String = "".to_string();
-                        if let Some(next_events) = event_dates.get_mut(&next_date.date_naive()) {
-                            next_events.sort_by(|a, b| {
-                                    a.start.as_ref()
-                                        .unwrap()
-                                        .date_time
-                                        .unwrap()
-                                        .cmp(&b.start.as_ref().unwrap().date_time.unwrap())
-                                });
-                            for next_event in next_events {
-                                let event_start = next_event.start.as_ref().unwrap().date_time.unwrap();
-                                let event_end = next_event.end.as_ref().unwrap().date_time.unwrap();
-                                let summary = next_event.summary.as_ref().unwrap().to_string();
-                                let formatted_event = format!(
-                                    "{:02}:{:02} - {:02}:{:02}: {}\n\n",
-                                    tz.from_utc_datetime(&event_start.naive_local()).hour(),
-                                    event_start.minute(),
-                                    tz.from_utc_datetime(&event_end.naive_local()).hour(),
-                                    event_end.minute(),
-                                    summary
-                                );
-
-                                if event_start.hour() < 12 {
-                                    write!(row_value_before_12, "{}", formatted_event).unwrap();
-                                } else {
-                                    write!(row_value_after_12, "{}", formatted_event).unwrap();
-                                }
-                            }
-                        }
-                        row_before_12.push(row_value_before_12);
-                        row_after_12.push(row_value_after_12);
-                    }
-
-                    table
-                        .set_header(header)
-                        .add_row(row_before_12)
-                        .add_row(row_after_12)
-                        .set_content_arrangement(ContentArrangement::DynamicFullWidth);
-
-                    println!("{table}");
-                }
-                Err(e) => println!("Error retrieving events: {:?}", e),
-            }
+        Some(("config", config_matches)) => {
+            return commands::config::run(config_matches)
+                .map_err(|e| ("Error managing the configuration", e))
         }
-        Some(("add", _)) | _ => {
-            let title = matches.get_one::<String>("title");
-            let date = matches.get_one::<String>("date");
-            let conference = matches.get_one::<bool>("conference");
-            if title.is_none() {
-                return;
-            }
-
-            if date.is_none() {
-                let result = hub
-                    .events()
-                    .quick_add("primary", title.as_ref().unwrap())
-                    .doit()
-                    .await;
+        Some(("profile", profile_matches)) => {
+            return commands::profile::run(profile_matches)
+                .await
+                .map_err(|e| ("Error managing profiles", e))
+        }
+        _ => {}
+    }
+    let config = get_config_path()
+        .and_then(|path| Config::load(&path))
+        .map_err(|e| ("Error reading the configuration", e))?;
+
+    let mut profiles: Vec<String> = matches
+        .get_many::<String>("profile")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    if profiles.is_empty() {
+        profiles.extend(config.profile.clone());
+    }
+    if profiles.len() > 1 && matches.subcommand_name() != Some("list") {
+        return Err((
+            "Error",
+            GcalError::Parse(
+                "--profile can only be given multiple times for the list command".to_string(),
+            )
+            .into(),
+        ));
+    }
+    let mut calendars: Vec<String> = matches
+        .get_many::<String>("calendar")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    // The calendars command lists and manages the calendars themselves, so it must keep
+    // working when the configured calendar was renamed or deleted.
+    if calendars.is_empty() && matches.subcommand_name() != Some("calendars") {
+        calendars.extend(config.calendar.clone());
+    }
 
-                match result {
-                    Ok((_, event)) => {
-                        println!("Event created: {:?}", event.html_link.unwrap().to_string())
-                    }
-                    Err(e) => {
-                        eprintln!("Error creating event: {:?}", e);
-                    }
-                }
-            } else {
-                let event_date_with_timezone = get_date_from_string(tz, date.unwrap());
-                let mut event = Event {
-                    summary: Some(title.unwrap().to_string()),
-                    start: Some(EventDateTime {
-                        date_time: Some(event_date_with_timezone),
-                        ..Default::default()
-                    }),
-                    end: Some(EventDateTime {
-                        date_time: Some(event_date_with_timezone + Duration::hours(1)),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                };
-                if conference.map_or(false, |&c| c) {
-                    event.conference_data = Some(ConferenceData {
-                        create_request: Some(CreateConferenceRequest {
-                            request_id: Some(Uuid::new_v4().to_string()),
-                            conference_solution_key: Some(ConferenceSolutionKey {
-                                type_: Some("hangoutsMeet".to_string()),
-                            }),
-                            ..Default::default()
-                        }),
-                        ..Default::default()
-                    });
-                }
-                    
-                let result = hub.events().insert(event, "primary").conference_data_version(1).doit().await;
+    let gcal_dir = get_gcal_dir().map_err(|e| ("Error reading the configuration", e))?;
+    let profiles: Vec<Option<String>> = match profiles.is_empty() {
+        true => vec![None],
+        false => profiles.into_iter().map(Some).collect(),
+    };
+    let mut accounts = vec![];
+    for profile in profiles {
+        let dir = match &profile {
+            Some(profile) => find_profile_dir(&gcal_dir, profile),
+            None => Ok(gcal_dir.clone()),
+        }
+        .map_err(|e| ("Error selecting the profile", e))?;
+        let hub = calendar::auth(&dir)
+            .await
+            .map_err(|e| ("Error connecting to Google Calendar", e))?;
+        let calendar_ids = calendar::resolve_calendar_ids(&hub, &calendars)
+            .await
+            .map_err(|e| ("Error resolving calendars", e))?;
+        accounts.push(Account {
+            profile,
+            hub,
+            calendar_ids,
+        });
+    }
+    let Account {
+        hub, calendar_ids, ..
+    } = &accounts[0];
+    let tz: Tz = match config.timezone() {
+        Some(tz) => tz,
+        None => get_default_timezone(hub)
+            .await
+            .map_err(|e| ("Error reading the time zone", e))?,
+    };
+    let calendar_id = calendar_ids[0].as_str();
+    if calendar_ids.len() > 1
+        && !matches!(
+            matches.subcommand_name(),
+            Some("list" | "search" | "next" | "now" | "free" | "export")
+        )
+    {
+        return Err((
+            "Error",
+            GcalError::Parse(
+                "--calendar can only be given multiple times for the list, search, next, now, free and export commands"
+                    .to_string(),
+            )
+            .into(),
+        ));
+    }
 
-                match result {
-                    Ok((_, event)) => {
-                        println!("Event created: {:?}", event.html_link.unwrap().to_string());
-                    }
-                    Err(e) => {
-                        eprintln!("Error creating event: {:?}", e);
-                    }
-                }
-            }
+    match matches.subcommand() {
+        Some(("list", list_matches)) => commands::list::run(&accounts, tz, &config, list_matches)
+            .await
+            .map_err(|e| ("Error retrieving events", e)),
+        Some(("delete", delete_matches)) => {
+            commands::delete::run(hub, tz, calendar_id, delete_matches)
+                .await
+                .map_err(|e| ("Error deleting event", e))
+        }
+        Some(("edit", edit_matches)) => commands::edit::run(hub, tz, calendar_id, edit_matches)
+            .await
+            .map_err(|e| ("Error editing event", e)),
+        Some(("show", show_matches)) => commands::show::run(hub, tz, calendar_id, show_matches)
+            .await
+            .map_err(|e| ("Error showing event", e)),
+        Some(("rsvp", rsvp_matches)) => commands::rsvp::run(hub, tz, calendar_id, rsvp_matches)
+            .await
+            .map_err(|e| ("Error answering invitation", e)),
+        Some(("search", search_matches)) => {
+            commands::search::run(hub, tz, calendar_ids, search_matches)
+                .await
+                .map_err(|e| ("Error searching events", e))
+        }
+        Some(("next", next_matches)) => commands::next::run(hub, tz, calendar_ids, next_matches)
+            .await
+            .map_err(|e| ("Error retrieving the next event", e)),
+        Some(("now", now_matches)) => commands::now::run(hub, tz, calendar_ids, now_matches)
+            .await
+            .map_err(|e| ("Error retrieving the current events", e)),
+        Some(("free", free_matches)) => {
+            commands::free::run(hub, tz, calendar_ids, &config, free_matches)
+                .await
+                .map_err(|e| ("Error finding free slots", e))
+        }
+        Some(("export", export_matches)) => {
+            commands::export::run(hub, tz, calendar_ids, &config, export_matches)
+                .await
+                .map_err(|e| ("Error exporting events", e))
+        }
+        Some(("import", import_matches)) => {
+            commands::import::run(hub, tz, calendar_id, import_matches)
+                .await
+                .map_err(|e| ("Error importing events", e))
+        }
+        Some(("calendars", calendars_matches)) => commands::calendars::run(hub, calendars_matches)
+            .await
+            .map_err(|e| ("Error managing calendars", e)),
+        subcommand => {
+            let matches = match subcommand {
+                Some(("add", add_matches)) => add_matches,
+                _ => matches,
+            };
+            commands::add::run(hub, tz, calendar_id, &config, matches)
+                .await
+                .map_err(|e| ("Error creating event", e))
         }
     }
 }
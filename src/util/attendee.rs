@@ -0,0 +1,254 @@
+use anyhow::{bail, Result};
+use google_calendar3::api::{Event, EventAttendee};
+
+use super::error::GcalError;
+
+/// The values accepted by `--notify`, as expected by the `sendUpdates` API parameter.
+pub const NOTIFY_OPTIONS: [&str; 3] = ["all", "external", "none"];
+
+/// The answers accepted by the `rsvp` command.
+pub const RSVP_RESPONSES: [&str; 3] = ["yes", "no", "maybe"];
+
+/// Returns the API response status of an `rsvp` answer.
+pub fn get_response_status(response: &str) -> &'static str {
+    match response {
+        "yes" => "accepted",
+        "no" => "declined",
+        _ => "tentative",
+    }
+}
+
+/// Returns `true` if the current user is invited to the event and has not answered yet.
+pub fn is_pending(event: &Event) -> bool {
+    event.attendees.iter().flatten().any(|attendee| {
+        attendee.self_ == Some(true) && attendee.response_status.as_deref() == Some("needsAction")
+    })
+}
+
+/// Returns `true` if the current user is invited to the event and has declined.
+pub fn is_declined(event: &Event) -> bool {
+    event.attendees.iter().flatten().any(|attendee| {
+        attendee.self_ == Some(true) && attendee.response_status.as_deref() == Some("declined")
+    })
+}
+
+/// Returns `true` if an attendee of the event matches the text, ignoring case.
+///
+/// The text may be a full email address, a part of it such as a domain, or a part of the
+/// attendee's display name.
+pub fn has_attendee(event: &Event, text: &str) -> bool {
+    let text = text.to_lowercase();
+    event.attendees.iter().flatten().any(|attendee| {
+        [&attendee.email, &attendee.display_name]
+            .into_iter()
+            .flatten()
+            .any(|value| value.to_lowercase().contains(&text))
+    })
+}
+
+/// Sets the response of the current user among the attendees of an event.
+///
+/// ## Arguments
+///
+/// * `attendees` - The current attendees of the event.
+/// * `response_status` - The new status, e.g. `accepted`.
+/// * `comment` - The comment sent along with the response, if any.
+///
+/// ## Returns
+///
+/// * `Result<Vec<EventAttendee>>` - The complete list of attendees, or an error if the current user is not invited.
+pub fn set_own_response(
+    mut attendees: Vec<EventAttendee>,
+    response_status: &str,
+    comment: Option<&str>,
+) -> Result<Vec<EventAttendee>> {
+    let Some(attendee) = attendees
+        .iter_mut()
+        .find(|attendee| attendee.self_ == Some(true))
+    else {
+        bail!(GcalError::NotFound(
+            "You are not an attendee of this event".to_string()
+        ));
+    };
+    attendee.response_status = Some(response_status.to_string());
+    if let Some(comment) = comment {
+        attendee.comment = Some(comment.to_string());
+    }
+    Ok(attendees)
+}
+
+/// Parses email addresses given as comma separated lists, e.g. `alice@x.com,bob@y.com`.
+///
+/// ## Arguments
+///
+/// * `values` - The values of a repeatable option, each holding one or more addresses.
+///
+/// ## Returns
+///
+/// * `Result<Vec<String>>` - The addresses in order, or an error if one of them is not a valid address.
+pub fn parse_emails<'a>(values: impl IntoIterator<Item = &'a String>) -> Result<Vec<String>> {
+    let mut emails = vec![];
+    for email in values
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|email| !email.is_empty())
+    {
+        let valid = email.split_once('@').is_some_and(|(user, domain)| {
+            !user.is_empty() && domain.contains('.') && !domain.starts_with('.')
+        });
+        if !valid || email.contains(char::is_whitespace) {
+            bail!(GcalError::Parse(format!(
+                "Invalid email address '{}'",
+                email
+            )));
+        }
+        emails.push(email.to_string());
+    }
+    Ok(emails)
+}
+
+/// Adds required and optional attendees to the attendees of an event.
+///
+/// Attendees already invited keep their response status; only whether they are optional is
+/// updated. Addresses are compared case-insensitively.
+///
+/// ## Arguments
+///
+/// * `attendees` - The current attendees of the event.
+/// * `required` - The addresses to invite as required attendees.
+/// * `optional` - The addresses to invite as optional attendees.
+///
+/// ## Returns
+///
+/// * `Vec<EventAttendee>` - The complete list of attendees, as expected by the API.
+pub fn merge_attendees(
+    mut attendees: Vec<EventAttendee>,
+    required: &[String],
+    optional: &[String],
+) -> Vec<EventAttendee> {
+    let invites = required
+        .iter()
+        .map(|email| (email, false))
+        .chain(optional.iter().map(|email| (email, true)));
+    for (email, is_optional) in invites {
+        let existing = attendees.iter_mut().find(|attendee| {
+            attendee
+                .email
+                .as_ref()
+                .is_some_and(|existing| existing.eq_ignore_ascii_case(email))
+        });
+        match existing {
+            Some(attendee) => attendee.optional = Some(is_optional),
+            None => attendees.push(EventAttendee {
+                email: Some(email.to_string()),
+                optional: Some(is_optional),
+                ..Default::default()
+            }),
+        }
+    }
+    attendees
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_emails() {
+        let values = vec![
+            "alice@x.com, bob@y.com".to_string(),
+            "carol@z.com".to_string(),
+        ];
+
+        assert_eq!(
+            parse_emails(&values).unwrap(),
+            vec!["alice@x.com", "bob@y.com", "carol@z.com"]
+        );
+        for invalid in ["alice", "@x.com", "alice@x", "alice@.com", "al ice@x.com"] {
+            assert!(parse_emails(&[invalid.to_string()]).is_err(), "{}", invalid);
+        }
+    }
+
+    fn attendee(email: &str, is_self: bool, response_status: &str) -> EventAttendee {
+        EventAttendee {
+            email: Some(email.to_string()),
+            self_: Some(is_self).filter(|is_self| *is_self),
+            response_status: Some(response_status.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_pending() {
+        let mut event = Event {
+            attendees: Some(vec![
+                attendee("organizer@x.com", false, "accepted"),
+                attendee("me@x.com", true, "needsAction"),
+            ]),
+            ..Default::default()
+        };
+        assert!(is_pending(&event));
+
+        event.attendees.as_mut().unwrap()[1].response_status = Some("declined".to_string());
+        assert!(!is_pending(&event));
+        assert!(is_declined(&event));
+        assert!(!is_declined(&Event::default()));
+        assert!(!is_pending(&Event::default()));
+    }
+
+    #[test]
+    fn test_has_attendee() {
+        let event = Event {
+            attendees: Some(vec![
+                EventAttendee {
+                    display_name: Some("Jane Doe".to_string()),
+                    ..attendee("jane@acme.com", false, "accepted")
+                },
+                attendee("me@x.com", true, "accepted"),
+            ]),
+            ..Default::default()
+        };
+        assert!(has_attendee(&event, "jane@acme.com"));
+        assert!(has_attendee(&event, "ACME"));
+        assert!(has_attendee(&event, "doe"));
+        assert!(!has_attendee(&event, "bob@acme.com"));
+        assert!(!has_attendee(&Event::default(), "acme"));
+    }
+
+    #[test]
+    fn test_set_own_response() {
+        let attendees = vec![
+            attendee("organizer@x.com", false, "accepted"),
+            attendee("me@x.com", true, "needsAction"),
+        ];
+
+        let attendees = set_own_response(attendees, "tentative", Some("Running late")).unwrap();
+
+        assert_eq!(attendees[0].response_status.as_deref(), Some("accepted"));
+        assert_eq!(attendees[1].response_status.as_deref(), Some("tentative"));
+        assert_eq!(attendees[1].comment.as_deref(), Some("Running late"));
+        assert!(set_own_response(attendees[..1].to_vec(), "accepted", None).is_err());
+    }
+
+    #[test]
+    fn test_merge_attendees() {
+        let existing = vec![EventAttendee {
+            email: Some("Alice@x.com".to_string()),
+            response_status: Some("accepted".to_string()),
+            ..Default::default()
+        }];
+
+        let attendees = merge_attendees(
+            existing,
+            &["bob@y.com".to_string()],
+            &["alice@x.com".to_string()],
+        );
+
+        assert_eq!(attendees.len(), 2);
+        assert_eq!(attendees[0].optional, Some(true));
+        assert_eq!(attendees[0].response_status.as_deref(), Some("accepted"));
+        assert_eq!(attendees[1].email.as_deref(), Some("bob@y.com"));
+        assert_eq!(attendees[1].optional, Some(false));
+    }
+}
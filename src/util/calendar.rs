@@ -1,32 +1,45 @@
-use std::{error::Error, path::Path};
+use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use chrono_tz::Tz;
 use google_calendar3::{
+    api::{CalendarListEntry, Event},
     hyper_rustls::{self, HttpsConnector},
     hyper_util::{self, client::legacy::connect::HttpConnector},
     yup_oauth2::{self, ApplicationSecret},
     CalendarHub,
 };
 
+use super::error::GcalError;
+use super::event::get_event_start;
 use super::file;
 
+/// The Google Calendar hub type used throughout the CLI.
+pub type Hub = CalendarHub<HttpsConnector<HttpConnector>>;
+
+/// The calendar used when no other calendar is specified.
+pub const PRIMARY_CALENDAR: &str = "primary";
+
 /// Authenticates the user with Google Calendar API and returns a CalendarHub instance.
 ///
+/// ## Arguments
+///
+/// * `dir` - The directory holding the Google application secret and the token store, see
+///   `profile::get_profile_dir`.
+///
 /// ## Returns
 ///
-/// * `Result<CalendarHub<HttpsConnector<HttpConnector>>, Box<dyn Error>>` - A result containing the CalendarHub instance or an error if any step fails.
+/// * `Result<Hub>` - A result containing the CalendarHub instance or an error if any step fails.
 ///
 /// ## Errors
 ///
-/// This function will return an error if:
-/// - The home directory cannot be determined.
-/// - The secret JSON file cannot be read.
-/// - The authenticator fails to build or retrieve tokens.
-/// - Any other I/O or network errors occur during these operations.
-pub async fn auth() -> Result<CalendarHub<HttpsConnector<HttpConnector>>, Box<dyn Error>> {
-    let secret_absolute_path = file::get_absolute_path(".gcal/secret.json")?;
-    let secret_path = std::path::Path::new(&secret_absolute_path);
+/// This function will return a `GcalError::Config` error if the token store cannot be opened.
+///
+/// And a `GcalError::Auth` error if the authenticator fails to retrieve a token.
+pub async fn auth(dir: &Path) -> Result<Hub> {
+    let secret_absolute_path = dir.join("secret.json");
+    let secret_path = secret_absolute_path.as_path();
     let _ = file::ensure_directory_exists(secret_path);
     let auth_builder = match read_google_secret(secret_path).await {
         Ok(secret) => yup_oauth2::InstalledFlowAuthenticator::builder(
@@ -56,11 +69,17 @@ pub async fn auth() -> Result<CalendarHub<HttpsConnector<HttpConnector>>, Box<dy
         }
     };
 
-    let store_path = file::get_absolute_path(".gcal/store.json")?;
+    let store_path = dir.join("store.json");
     let auth = auth_builder
         .persist_tokens_to_disk(&store_path)
         .build()
-        .await?;
+        .await
+        .map_err(|e| {
+            GcalError::Config(format!(
+                "Failed to open the token store {:?}: {}",
+                store_path, e
+            ))
+        })?;
 
     let scopes = &[
         "https://www.googleapis.com/auth/calendar",
@@ -69,38 +88,335 @@ pub async fn auth() -> Result<CalendarHub<HttpsConnector<HttpConnector>>, Box<dy
         "https://www.googleapis.com/auth/calendar.events.readonly",
     ];
 
-    match auth.token(scopes).await {
-        Ok(_) => {}
-        Err(e) => println!("Authentication error: {:?}", e),
-    }
-    let client = hyper_util::client::legacy::Client::builder(
-        hyper_util::rt::TokioExecutor::new()
-    )
-    .build(
-        hyper_rustls::HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .unwrap()
-            .https_or_http()
-            .enable_http1()
-            .build()
-    );
+    auth.token(scopes)
+        .await
+        .map_err(|e| GcalError::Auth(e.to_string()))?;
+    let client = hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
+        .build(
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_native_roots()
+                .map_err(|e| {
+                    GcalError::Network(format!("Failed to load the root certificates: {}", e))
+                })?
+                .https_or_http()
+                .enable_http1()
+                .build(),
+        );
 
     let hub = CalendarHub::new(client, auth);
     Ok(hub)
 }
 
-pub async fn get_default_timezone(hub: &CalendarHub<HttpsConnector<HttpConnector>>) -> Result<Tz> {
-    let result = hub.settings().list().doit().await;
-    let settings = result.unwrap().1.items.unwrap_or_default();
+/// Returns the time zone set in the user's Google Calendar settings.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+///
+/// ## Returns
+///
+/// * `Result<Tz>` - The time zone, or an error if the settings cannot be read or the time zone is unknown.
+pub async fn get_default_timezone(hub: &Hub) -> Result<Tz> {
+    let (_, settings) = hub
+        .settings()
+        .get("timezone")
+        .doit()
+        .await
+        .context("Failed to read the calendar settings")?;
+    let timezone = settings.value.unwrap_or_default();
+    let tz = timezone.parse::<Tz>().map_err(|_| GcalError::Api {
+        status: None,
+        message: format!("Unknown time zone '{}' in the calendar settings", timezone),
+    })?;
+    Ok(tz)
+}
+
+/// Returns every calendar in the user's calendar list.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+///
+/// ## Returns
+///
+/// * `Result<Vec<CalendarListEntry>>` - The calendars, or an error if the request fails.
+pub async fn get_calendar_list(hub: &Hub) -> Result<Vec<CalendarListEntry>> {
+    let mut calendars = vec![];
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut call = hub.calendar_list().list();
+        if let Some(token) = &page_token {
+            call = call.page_token(token);
+        }
+        let (_, calendar_list) = call.doit().await.context("Failed to list calendars")?;
+        calendars.extend(calendar_list.items.unwrap_or_default());
+        page_token = calendar_list.next_page_token;
+        if page_token.is_none() {
+            return Ok(calendars);
+        }
+    }
+}
 
-    let timezone_setting = settings
+/// Resolves calendar IDs or human readable names to calendar IDs.
+///
+/// `primary` is passed through as is, every other value is looked up in the user's calendar
+/// list by ID or by name. Values looking like an email address are accepted as IDs even if
+/// they are not part of the calendar list, so that other people's calendars can be used.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+/// * `calendars` - The calendar IDs or names given by the user. If empty, the primary calendar is used.
+///
+/// ## Returns
+///
+/// * `Result<Vec<String>>` - The calendar IDs, in the order they were given.
+///
+/// ## Errors
+///
+/// This function will return an error if the calendar list cannot be retrieved or a name
+/// does not match any calendar.
+pub async fn resolve_calendar_ids(hub: &Hub, calendars: &[String]) -> Result<Vec<String>> {
+    if calendars.is_empty() {
+        return Ok(vec![PRIMARY_CALENDAR.to_string()]);
+    }
+    if calendars
         .iter()
-        .find(|setting| setting.id == Some("timezone".to_string()))
-        .ok_or("Timezone setting not found");
+        .all(|calendar| calendar == PRIMARY_CALENDAR)
+    {
+        return Ok(calendars.to_vec());
+    }
 
-    let timezone = timezone_setting.unwrap();
-    let tz: Tz = timezone.value.as_ref().unwrap().parse().unwrap();
-    Ok(tz)
+    let calendar_list = get_calendar_list(hub).await?;
+    let mut calendar_ids = vec![];
+    for calendar in calendars {
+        match find_calendar_id(&calendar_list, calendar) {
+            Some(id) => calendar_ids.push(id),
+            None if calendar == PRIMARY_CALENDAR || calendar.contains('@') => {
+                calendar_ids.push(calendar.to_string())
+            }
+            None => bail!(GcalError::NotFound(format!(
+                "Calendar '{}' not found",
+                calendar
+            ))),
+        }
+    }
+    Ok(calendar_ids)
+}
+
+/// Finds the ID of the calendar whose ID or name matches the given value.
+///
+/// Names are compared case-insensitively, both against the user's own name for the calendar
+/// and against its original name.
+fn find_calendar_id(calendar_list: &[CalendarListEntry], calendar: &str) -> Option<String> {
+    let name = calendar.to_lowercase();
+    calendar_list
+        .iter()
+        .find(|entry| entry.id.as_deref() == Some(calendar))
+        .or_else(|| {
+            calendar_list.iter().find(|entry| {
+                [&entry.summary_override, &entry.summary]
+                    .iter()
+                    .any(|summary| summary.as_ref().is_some_and(|s| s.to_lowercase() == name))
+            })
+        })
+        .and_then(|entry| entry.id.clone())
+}
+
+/// The maximum number of events requested per page, as allowed by the API.
+const MAX_EVENTS_PER_PAGE: usize = 2500;
+
+/// Lists the events of a calendar in a time window, following page tokens until every
+/// event has been retrieved or the limit is reached.
+///
+/// Recurring events are expanded into their instances and the results are ordered by start time.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+/// * `calendar_id` - The calendar to list the events of.
+/// * `time_min` - Lower bound (exclusive) for the end time of the events.
+/// * `time_max` - Upper bound (exclusive) for the start time of the events.
+/// * `query` - Optional free text matched by the API against summary, description, location and attendees.
+/// * `limit` - Optional maximum number of events to return.
+///
+/// ## Returns
+///
+/// * `Result<Vec<Event>>` - The events, or an error if any of the requests fails.
+pub async fn list_events(
+    hub: &Hub,
+    calendar_id: &str,
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    query: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<Event>> {
+    fetch_events(hub, calendar_id, (time_min, time_max), query, limit, true).await
+}
+
+/// Lists the events of a calendar in a time window without expanding recurring events.
+///
+/// Each recurring event is returned once with its recurrence rules, along with its
+/// modified instances. The events are not ordered.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+/// * `calendar_id` - The calendar to list the events of.
+/// * `time_min` - Lower bound (exclusive) for the end time of the events.
+/// * `time_max` - Upper bound (exclusive) for the start time of the events.
+///
+/// ## Returns
+///
+/// * `Result<Vec<Event>>` - The events, or an error if any of the requests fails.
+pub async fn list_event_series(
+    hub: &Hub,
+    calendar_id: &str,
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+) -> Result<Vec<Event>> {
+    fetch_events(hub, calendar_id, (time_min, time_max), None, None, false).await
+}
+
+/// Follows the pages of an event listing, see `list_events` and `list_event_series`.
+async fn fetch_events(
+    hub: &Hub,
+    calendar_id: &str,
+    (time_min, time_max): (DateTime<Utc>, DateTime<Utc>),
+    query: Option<&str>,
+    limit: Option<usize>,
+    single_events: bool,
+) -> Result<Vec<Event>> {
+    let mut events: Vec<Event> = vec![];
+    let mut page_token: Option<String> = None;
+    loop {
+        let remaining = limit.map_or(MAX_EVENTS_PER_PAGE, |limit| limit - events.len());
+        let mut call = hub
+            .events()
+            .list(calendar_id)
+            .time_min(time_min)
+            .time_max(time_max)
+            .single_events(single_events)
+            .max_results(remaining.clamp(1, MAX_EVENTS_PER_PAGE) as i32);
+        // Events can only be ordered by start time once recurring events are expanded.
+        if single_events {
+            call = call.order_by("startTime");
+        }
+        if let Some(query) = query {
+            call = call.q(query);
+        }
+        if let Some(token) = &page_token {
+            call = call.page_token(token);
+        }
+        let (_, page) = call
+            .doit()
+            .await
+            .with_context(|| format!("Failed to list events of calendar {}", calendar_id))?;
+        events.extend(page.items.unwrap_or_default());
+        page_token = page.next_page_token;
+        let limit_reached = limit.is_some_and(|limit| events.len() >= limit);
+        if page_token.is_none() || limit_reached {
+            events.truncate(limit.unwrap_or(events.len()));
+            return Ok(events);
+        }
+    }
+}
+
+/// Lists the events of several calendars in a time window, merged and ordered by start time.
+///
+/// ## Arguments
+///
+/// * `hub` - The authenticated CalendarHub instance.
+/// * `tz` - The timezone in which all-day events are placed when ordering.
+/// * `calendar_ids` - The calendars to list the events of.
+/// * `time_min` - Lower bound (exclusive) for the end time of the events.
+/// * `time_max` - Upper bound (exclusive) for the start time of the events.
+/// * `query` - Optional free text matched by the API against summary, description, location and attendees.
+/// * `limit` - Optional maximum number of events to return in total.
+///
+/// ## Returns
+///
+/// * `Result<Vec<(String, Event)>>` - The events along with the ID of their calendar.
+pub async fn list_events_of_calendars(
+    hub: &Hub,
+    tz: Tz,
+    calendar_ids: &[String],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    query: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<(String, Event)>> {
+    let mut events: Vec<(String, Event)> = vec![];
+    for calendar_id in calendar_ids {
+        let calendar_events =
+            list_events(hub, calendar_id, time_min, time_max, query, limit).await?;
+        events.extend(
+            calendar_events
+                .into_iter()
+                .map(|event| (calendar_id.to_string(), event)),
+        );
+    }
+    events.sort_by_key(|(_, event)| get_event_start(event, tz));
+    events.truncate(limit.unwrap_or(events.len()));
+    Ok(events)
+}
+
+/// A signed-in Google account with the calendars selected in it.
+pub struct Account {
+    /// The profile the account was signed in with, or `None` without a profile.
+    pub profile: Option<String>,
+    pub hub: Hub,
+    pub calendar_ids: Vec<String>,
+}
+
+/// Lists the events of the selected calendars of several accounts, merged and ordered by
+/// start time.
+///
+/// With more than one account, the calendars are labelled `profile:calendar` so that the
+/// primary calendars of different accounts can be told apart.
+///
+/// ## Arguments
+///
+/// * `accounts` - The accounts and their calendars.
+/// * `tz` - The timezone in which all-day events are placed when ordering.
+/// * `time_min` - Lower bound (exclusive) for the end time of the events.
+/// * `time_max` - Upper bound (exclusive) for the start time of the events.
+/// * `limit` - Optional maximum number of events to return in total.
+///
+/// ## Returns
+///
+/// * `Result<Vec<(String, Event)>>` - The events along with the label of their calendar.
+pub async fn list_events_of_accounts(
+    accounts: &[Account],
+    tz: Tz,
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    limit: Option<usize>,
+) -> Result<Vec<(String, Event)>> {
+    let mut events: Vec<(String, Event)> = vec![];
+    for account in accounts {
+        let account_events = list_events_of_calendars(
+            &account.hub,
+            tz,
+            &account.calendar_ids,
+            time_min,
+            time_max,
+            None,
+            limit,
+        )
+        .await?;
+        events.extend(account_events.into_iter().map(
+            |(calendar_id, event)| match &account.profile {
+                Some(profile) if accounts.len() > 1 => {
+                    (format!("{}:{}", profile, calendar_id), event)
+                }
+                _ => (calendar_id, event),
+            },
+        ));
+    }
+    events.sort_by_key(|(_, event)| get_event_start(event, tz));
+    events.truncate(limit.unwrap_or(events.len()));
+    Ok(events)
 }
 
 /// Reads the Google application secret from the specified path.
@@ -131,3 +447,44 @@ async fn read_google_secret(path: &Path) -> Result<ApplicationSecret> {
         })?;
     Ok(secret)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(id: &str, summary: &str, summary_override: Option<&str>) -> CalendarListEntry {
+        CalendarListEntry {
+            id: Some(id.to_string()),
+            summary: Some(summary.to_string()),
+            summary_override: summary_override.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_calendar_id() {
+        let calendar_list = vec![
+            calendar("me@example.com", "me@example.com", None),
+            calendar("team@group.calendar.google.com", "Team", None),
+            calendar(
+                "oncall@group.calendar.google.com",
+                "SRE rota",
+                Some("On-call"),
+            ),
+        ];
+
+        assert_eq!(
+            find_calendar_id(&calendar_list, "team"),
+            Some("team@group.calendar.google.com".to_string())
+        );
+        assert_eq!(
+            find_calendar_id(&calendar_list, "On-Call"),
+            Some("oncall@group.calendar.google.com".to_string())
+        );
+        assert_eq!(
+            find_calendar_id(&calendar_list, "me@example.com"),
+            Some("me@example.com".to_string())
+        );
+        assert_eq!(find_calendar_id(&calendar_list, "Holidays"), None);
+    }
+}
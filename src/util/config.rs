@@ -0,0 +1,413 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use google_calendar3::api::{EventReminder, EventReminders};
+use serde::{Deserialize, Serialize};
+
+use super::date::{parse_duration, parse_time_range};
+use super::error::GcalError;
+use super::file;
+use super::output::OUTPUT_FORMATS;
+use super::profile::check_profile_name;
+
+/// The path of the configuration file, relative to the home directory.
+pub const CONFIG_PATH: &str = ".gcal/config.toml";
+
+/// The keys of the configuration file, with a description of their values.
+pub const CONFIG_KEYS: [(&str, &str); 9] = [
+    (
+        "calendar",
+        "Calendar ID or name used instead of the primary calendar",
+    ),
+    (
+        "duration",
+        "Duration of new timed events, e.g. 30m or 1h30m",
+    ),
+    (
+        "conference",
+        "Whether new events get a Google Meet conference, true or false",
+    ),
+    ("reminders", "Popup reminders of new events, e.g. 10m,1h"),
+    ("week_start", "First day of the week, e.g. monday or sunday"),
+    (
+        "working_hours",
+        "Part of each day searched by free, e.g. 09:00-17:00",
+    ),
+    (
+        "timezone",
+        "Time zone used instead of the calendar's, e.g. Europe/Budapest",
+    ),
+    (
+        "format",
+        "Output format of list: table, json, ndjson, csv or tsv",
+    ),
+    ("profile", "Profile used when no --profile is given"),
+];
+
+/// The duration of new timed events when none is configured.
+const DEFAULT_DURATION: &str = "1h";
+/// The part of each day searched for free slots when none is configured.
+const DEFAULT_WORKING_HOURS: &str = "09:00-17:00";
+
+/// The defaults read from the configuration file. Unset keys keep the built-in defaults.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub calendar: Option<String>,
+    pub duration: Option<String>,
+    pub conference: Option<bool>,
+    pub reminders: Option<Vec<String>>,
+    pub week_start: Option<String>,
+    pub working_hours: Option<String>,
+    pub timezone: Option<String>,
+    pub format: Option<String>,
+    pub profile: Option<String>,
+}
+
+/// The settings applied to every new event.
+#[derive(Debug, Clone)]
+pub struct EventDefaults {
+    pub duration: Duration,
+    pub conference: bool,
+    pub reminders: Option<EventReminders>,
+}
+
+/// Returns the absolute path of the configuration file.
+pub fn get_config_path() -> Result<PathBuf> {
+    file::get_absolute_path(CONFIG_PATH).map_err(|e| anyhow!(GcalError::Config(e.to_string())))
+}
+
+impl Config {
+    /// Reads the configuration file, returning the built-in defaults if it does not exist.
+    ///
+    /// ## Arguments
+    ///
+    /// * `path` - The path of the configuration file.
+    ///
+    /// ## Returns
+    ///
+    /// * `Result<Config>` - The configuration, or an error if the file cannot be read or has an
+    ///   unknown key or an invalid value.
+    pub fn load(path: &Path) -> Result<Config> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => bail!(GcalError::Config(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            ))),
+        };
+        let invalid = |message: String| {
+            anyhow!(GcalError::Config(format!(
+                "Invalid configuration in {}: {}",
+                path.display(),
+                message
+            )))
+        };
+        let config: Config =
+            toml::from_str(&content).map_err(|e| invalid(e.message().to_string()))?;
+        for (key, _) in CONFIG_KEYS {
+            if let Some(value) = config.get(key)? {
+                Config::default()
+                    .set(key, &value)
+                    .map_err(|e| invalid(format!("{}: {}", key, e)))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Writes the configuration file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string(self).map_err(|e| {
+            anyhow!(GcalError::Config(format!(
+                "Failed to write the configuration: {}",
+                e
+            )))
+        })?;
+        let _ = file::ensure_directory_exists(path);
+        fs::write(path, content).map_err(|e| {
+            anyhow!(GcalError::Config(format!(
+                "Failed to write {}: {}",
+                path.display(),
+                e
+            )))
+        })
+    }
+
+    /// Returns the value of a key as it is given to `set`, or `None` if it is not set.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let value = match check_key(key)? {
+            "calendar" => self.calendar.clone(),
+            "duration" => self.duration.clone(),
+            "conference" => self.conference.map(|conference| conference.to_string()),
+            "reminders" => self.reminders.as_ref().map(|reminders| reminders.join(",")),
+            "week_start" => self.week_start.clone(),
+            "working_hours" => self.working_hours.clone(),
+            "timezone" => self.timezone.clone(),
+            "format" => self.format.clone(),
+            _ => self.profile.clone(),
+        };
+        Ok(value)
+    }
+
+    /// Validates and sets the value of a key.
+    ///
+    /// ## Arguments
+    ///
+    /// * `key` - One of the `CONFIG_KEYS`.
+    /// * `value` - The new value, in the format described by `CONFIG_KEYS`.
+    ///
+    /// ## Returns
+    ///
+    /// * `Result<()>` - Ok(()) if the value was set, or an error if the key is unknown or the
+    ///   value invalid.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let invalid = |expected: &str| {
+            anyhow!(GcalError::Parse(format!(
+                "Invalid {} '{}', expected {}",
+                key, value, expected
+            )))
+        };
+        match check_key(key)? {
+            "calendar" if value.is_empty() => return Err(invalid("a calendar ID or name")),
+            "calendar" => self.calendar = Some(value.to_string()),
+            "duration" => {
+                parse_duration(value)?;
+                self.duration = Some(value.to_string());
+            }
+            "conference" => {
+                self.conference = Some(match value.to_lowercase().as_str() {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => return Err(invalid("true or false")),
+                })
+            }
+            "reminders" => {
+                let reminders: Vec<String> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|reminder| !reminder.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                for reminder in &reminders {
+                    get_reminder_minutes(reminder)?;
+                }
+                self.reminders = Some(reminders);
+            }
+            "week_start" => {
+                value
+                    .parse::<Weekday>()
+                    .map_err(|_| invalid("a day of the week"))?;
+                self.week_start = Some(value.to_lowercase());
+            }
+            "working_hours" => {
+                parse_time_range(value)?;
+                self.working_hours = Some(value.to_string());
+            }
+            "timezone" => {
+                value
+                    .parse::<Tz>()
+                    .map_err(|_| invalid("an IANA time zone such as Europe/Budapest"))?;
+                self.timezone = Some(value.to_string());
+            }
+            "format" => {
+                if !OUTPUT_FORMATS.contains(&value) {
+                    return Err(invalid(&OUTPUT_FORMATS.join(", ")));
+                }
+                self.format = Some(value.to_string());
+            }
+            _ => {
+                check_profile_name(value)?;
+                self.profile = Some(value.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a key, restoring its built-in default.
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        match check_key(key)? {
+            "calendar" => self.calendar = None,
+            "duration" => self.duration = None,
+            "conference" => self.conference = None,
+            "reminders" => self.reminders = None,
+            "week_start" => self.week_start = None,
+            "working_hours" => self.working_hours = None,
+            "timezone" => self.timezone = None,
+            "format" => self.format = None,
+            _ => self.profile = None,
+        }
+        Ok(())
+    }
+
+    /// Returns the settings of new events: one hour long, without a conference and with the
+    /// calendar's reminders unless configured otherwise.
+    pub fn event_defaults(&self) -> Result<EventDefaults> {
+        let reminders = match &self.reminders {
+            Some(reminders) => Some(EventReminders {
+                use_default: Some(false),
+                overrides: Some(
+                    reminders
+                        .iter()
+                        .map(|reminder| {
+                            let minutes = get_reminder_minutes(reminder).map_err(|e| {
+                                anyhow!(GcalError::Config(format!("Invalid reminder: {}", e)))
+                            })?;
+                            Ok(EventReminder {
+                                method: Some("popup".to_string()),
+                                minutes: Some(minutes),
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                ),
+            }),
+            None => None,
+        };
+        Ok(EventDefaults {
+            duration: parse_duration(self.duration.as_deref().unwrap_or(DEFAULT_DURATION))?,
+            conference: self.conference.unwrap_or(false),
+            reminders,
+        })
+    }
+
+    /// Returns the first day of the week, Monday unless configured otherwise.
+    pub fn week_start(&self) -> Weekday {
+        self.week_start
+            .as_deref()
+            .and_then(|day| day.parse().ok())
+            .unwrap_or(Weekday::Mon)
+    }
+
+    /// Returns the part of each day searched for free slots.
+    pub fn working_hours(&self) -> Result<(NaiveTime, NaiveTime)> {
+        parse_time_range(
+            self.working_hours
+                .as_deref()
+                .unwrap_or(DEFAULT_WORKING_HOURS),
+        )
+    }
+
+    /// Returns the configured time zone, if any.
+    pub fn timezone(&self) -> Option<Tz> {
+        self.timezone.as_deref().and_then(|tz| tz.parse().ok())
+    }
+}
+
+fn check_key(key: &str) -> Result<&'static str> {
+    CONFIG_KEYS
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| *name == key)
+        .ok_or_else(|| {
+            anyhow!(GcalError::Parse(format!(
+                "Unknown configuration key '{}', expected one of {}",
+                key,
+                CONFIG_KEYS.map(|(name, _)| name).join(", ")
+            )))
+        })
+}
+
+/// Parses a reminder such as `10m` or `1h` into the minutes before the event.
+fn get_reminder_minutes(reminder: &str) -> Result<i32> {
+    i32::try_from(parse_duration(reminder)?.num_minutes()).map_err(|_| {
+        anyhow!(GcalError::Parse(format!(
+            "The reminder '{}' is too long",
+            reminder
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_set_and_get() {
+        let mut config = Config::default();
+
+        config.set("duration", "30m").unwrap();
+        config.set("conference", "yes").unwrap();
+        config.set("reminders", "10m, 1h").unwrap();
+        config.set("week_start", "Sunday").unwrap();
+        config.set("timezone", "Europe/Budapest").unwrap();
+
+        assert_eq!(config.get("duration").unwrap().as_deref(), Some("30m"));
+        assert_eq!(config.get("conference").unwrap().as_deref(), Some("true"));
+        assert_eq!(config.get("reminders").unwrap().as_deref(), Some("10m,1h"));
+        assert_eq!(config.get("format").unwrap(), None);
+        assert_eq!(config.week_start(), Weekday::Sun);
+        assert_eq!(config.timezone(), Some(chrono_tz::Europe::Budapest));
+
+        let defaults = config.event_defaults().unwrap();
+        assert_eq!(defaults.duration, Duration::minutes(30));
+        assert!(defaults.conference);
+        let minutes: Vec<i32> = defaults
+            .reminders
+            .and_then(|reminders| reminders.overrides)
+            .unwrap()
+            .iter()
+            .filter_map(|reminder| reminder.minutes)
+            .collect();
+        assert_eq!(minutes, vec![10, 60]);
+
+        config.unset("duration").unwrap();
+        assert_eq!(config.get("duration").unwrap(), None);
+        assert_eq!(
+            config.event_defaults().unwrap().duration,
+            Duration::hours(1)
+        );
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let mut config = Config::default();
+
+        assert!(config.set("colour", "red").is_err());
+        assert!(config.get("colour").is_err());
+        assert!(config.set("duration", "soon").is_err());
+        assert!(config.set("conference", "maybe").is_err());
+        assert!(config.set("reminders", "10m,later").is_err());
+        assert!(config.set("reminders", "9999999999m").is_err());
+        assert!(config.set("week_start", "someday").is_err());
+        assert!(config.set("working_hours", "17:00-09:00").is_err());
+        assert!(config.set("timezone", "Mars/Olympus").is_err());
+        assert!(config.set("format", "xml").is_err());
+        assert!(config.set("profile", "../work").is_err());
+        assert_eq!(config, Config::default());
+
+        let config = Config {
+            reminders: Some(vec!["9999999999m".to_string()]),
+            ..Default::default()
+        };
+        assert!(config.event_defaults().is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = env::temp_dir().join("test_gcal_config/config.toml");
+        let _ = fs::remove_file(&path);
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        let mut config = Config::default();
+        config.set("calendar", "Work").unwrap();
+        config.set("reminders", "15m").unwrap();
+        config.set("working_hours", "08:00-16:00").unwrap();
+        config.save(&path).unwrap();
+
+        assert_eq!(Config::load(&path).unwrap(), config);
+
+        fs::write(&path, "duration = \"forever\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+        fs::write(&path, "colour = \"red\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
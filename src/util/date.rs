@@ -1,22 +1,27 @@
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, Utc, TimeZone};
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc, Weekday,
+};
 use chrono_tz::Tz;
 
+use super::error::GcalError;
+
 /// Returns the start of the current week as a `DateTime<Local>`.
 ///
 /// This function calculates the start of the week based on the current local
-/// time. The week starts on Monday.
+/// time. The week starts on the given day, usually Monday.
 ///
 /// # Examples
 ///
 /// ```
-/// let start_of_week = get_start_of_the_week();
+/// let start_of_week = get_start_of_the_week(Weekday::Mon);
 /// println!("Start of the week: {}", start_of_week);
 /// ```
-pub fn get_start_of_the_week() -> DateTime<Local> {
+pub fn get_start_of_the_week(week_start: Weekday) -> DateTime<Local> {
     let now = Local::now();
-    let days_to_subtract = now.weekday().num_days_from_monday() as i64;
-    let start_of_the_week = now - Duration::days(days_to_subtract);
-    start_of_the_week
+    let days_to_subtract = now.weekday().days_since(week_start) as i64;
+    now - Duration::days(days_to_subtract)
 }
 
 /// Returns a vector of strings containing the names of the days of the week in English.
@@ -39,7 +44,7 @@ pub fn get_start_of_the_week() -> DateTime<Local> {
 /// ]);
 /// ```
 pub fn days_in_english() -> [&'static str; 7] {
-    let days = [
+    [
         "Monday",
         "Tuesday",
         "Wednesday",
@@ -47,18 +52,22 @@ pub fn days_in_english() -> [&'static str; 7] {
         "Friday",
         "Saturday",
         "Sunday",
-    ];
-
-    days
+    ]
 }
 
 /// Converts a date string to a `DateTime<Utc>` based on the provided timezone.
 ///
-/// This function accepts a date string that can be either in the format `HH:MM`,
-/// `YYYY-MM-DD HH:MM`, or `MM-DD HH:MM`. It will parse the string and convert it to a
-/// `DateTime<Utc>` considering the given timezone. If the string is in the `HH:MM` format,
-/// it will use the current date combined with the provided time. If the string is in the
-/// `MM-DD HH:MM` format, it will use the current year combined with the provided month, day, and time.
+/// Besides the fixed `HH:MM`, `YYYY-MM-DD HH:MM` and `MM-DD HH:MM` formats, this function
+/// understands:
+///
+/// - relative days: `today`, `tomorrow` and `yesterday`,
+/// - weekday names: `fri` or `friday` is the next Friday including today, `next fri` the
+///   next one after today,
+/// - relative offsets: `in 2h`, `in 30 minutes`, `+90m`, or `+3d 14:00` for a day offset
+///   combined with a time,
+/// - 12-hour clock times such as `9am` or `9:30 pm`, and `noon` or `midnight`.
+///
+/// A date without a time, e.g. `tomorrow`, is rejected since events need a start time.
 ///
 /// # Arguments
 ///
@@ -67,7 +76,8 @@ pub fn days_in_english() -> [&'static str; 7] {
 ///
 /// # Returns
 ///
-/// A `DateTime<Utc>` representing the parsed date and time in UTC.
+/// A `Result` containing the parsed date and time in UTC, or an error describing which part
+/// of the string could not be understood.
 ///
 /// # Examples
 ///
@@ -75,54 +85,613 @@ pub fn days_in_english() -> [&'static str; 7] {
 /// use chrono_tz::Tz;
 ///
 /// let tz: Tz = "America/New_York".parse().unwrap();
-/// let date_str = String::from("2024-07-27 15:30");
-/// let utc_date = get_date_from_string(tz, &date_str);
-/// println!("{}", utc_date); // Outputs the parsed date and time in UTC
-///
-/// let time_str = String::from("15:30");
-/// let utc_time = get_date_from_string(tz, &time_str);
-/// println!("{}", utc_time); // Outputs the current date with the provided time in UTC
-///
-/// let month_day_time_str = String::from("07-27 15:30");
-/// let utc_month_day_time = get_date_from_string(tz, &month_day_time_str);
-/// println!("{}", utc_month_day_time); // Outputs the current year with the provided month, day, and time in UTC
-/// ```
-pub fn get_date_from_string(tz: Tz, date: &String) -> DateTime<Utc> {
-    if let Ok(parsed_time) = NaiveTime::parse_from_str(date, "%H:%M") {
-        let current_date = Utc::now().date_naive();
-        let combined_naive = NaiveDateTime::new(current_date, parsed_time);
-        let event_date_with_timezone = tz
-            .from_local_datetime(&combined_naive)
-            .unwrap()
-            .naive_utc()
-            .and_utc();
-        return event_date_with_timezone;
-    } else if let Ok(parsed_time) = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M") {
-        let event_date_with_timezone = tz
-            .from_local_datetime(&parsed_time)
-            .unwrap()
-            .naive_utc()
-            .and_utc();
-        return event_date_with_timezone;
-    } else {
-        let parsed_time = NaiveDateTime::parse_from_str(
-            &format!("{}-{}", Utc::now().year(), date),
-            "%Y-%m-%d %H:%M",
-        );
-        print!("{}", &format!("{}-{}", Utc::now().year(), date));
-        let combined_naive = parsed_time.unwrap();
-        let event_date_with_timezone = tz
-            .from_local_datetime(&combined_naive)
-            .unwrap()
-            .naive_utc()
-            .and_utc();
-        return event_date_with_timezone;
+/// let utc_date = get_date_from_string(tz, "2024-07-27 15:30").unwrap();
+/// println!("{}", utc_date); // 2024-07-27 19:30:00 UTC
+///
+/// let utc_time = get_date_from_string(tz, "next fri 9am").unwrap();
+/// println!("{}", utc_time); // Outputs 9:00 in New York on the next Friday, in UTC
+/// ```
+pub fn get_date_from_string(tz: Tz, date: &str) -> Result<DateTime<Utc>> {
+    let now = Utc::now().with_timezone(&tz).naive_local();
+    let local = parse_local_date_time(now, date)?;
+    tz.from_local_datetime(&local)
+        .earliest()
+        .map(|date_time| date_time.to_utc())
+        .ok_or_else(|| {
+            anyhow!(GcalError::Parse(format!(
+                "The time '{}' does not exist in {}",
+                date,
+                tz.name()
+            )))
+        })
+}
+
+/// Parses a date string relative to `now`, see `get_date_from_string` for the supported formats.
+fn parse_local_date_time(now: NaiveDateTime, input: &str) -> Result<NaiveDateTime> {
+    let text = input.trim().to_lowercase();
+    let unknown = |part: &str| {
+        anyhow!(GcalError::Parse(format!(
+            "Could not understand '{}' in '{}', expected e.g. 14:00, 2024-07-27 14:00, \
+             tomorrow 10:00, next fri 9am, in 2h or +3d 14:00",
+            part, input
+        )))
+    };
+    let out_of_range = || {
+        anyhow!(GcalError::Parse(format!(
+            "The date '{}' is out of range",
+            input
+        )))
+    };
+    let add_offset = |offset: Option<Duration>, parsed: Duration| {
+        offset
+            .unwrap_or_default()
+            .checked_add(&parsed)
+            .ok_or_else(out_of_range)
+    };
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Err(anyhow!(GcalError::Parse("No date given".to_string())));
+    }
+
+    let mut day: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut offset: Option<Duration> = None;
+    let mut set_day = |date: NaiveDate, part: &str| match day.replace(date) {
+        Some(_) => Err(anyhow!(GcalError::Parse(format!(
+            "The day is given more than once in '{}'",
+            part
+        )))),
+        None => Ok(()),
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let next = tokens.get(i + 1).copied();
+        i += 1;
+        match token {
+            "at" | "on" => {}
+            "today" => set_day(now.date(), input)?,
+            "tomorrow" | "tmrw" => set_day(now.date() + Duration::days(1), input)?,
+            "yesterday" => set_day(now.date() - Duration::days(1), input)?,
+            "next" | "this" => {
+                let weekday = next
+                    .and_then(|next| next.parse::<Weekday>().ok())
+                    .ok_or_else(|| unknown(token))?;
+                set_day(
+                    get_next_weekday(now.date(), weekday, token == "next"),
+                    input,
+                )?;
+                i += 1;
+            }
+            "in" => {
+                let (parsed, consumed) =
+                    parse_offset(&tokens[i..]).ok_or_else(|| unknown(token))?;
+                offset = Some(add_offset(offset, parsed)?);
+                i += consumed;
+            }
+            _ if token.starts_with('+') => {
+                let amount = &token[1..];
+                let (parsed, consumed) = match parse_offset(&[amount]) {
+                    Some(parsed) => parsed,
+                    None => parse_offset(&[amount, next.unwrap_or_default()])
+                        .filter(|(_, consumed)| *consumed == 2)
+                        .ok_or_else(|| unknown(token))?,
+                };
+                offset = Some(add_offset(offset, parsed)?);
+                i += consumed - 1;
+            }
+            _ => {
+                if let Ok(weekday) = token.parse::<Weekday>() {
+                    set_day(get_next_weekday(now.date(), weekday, false), input)?;
+                } else if let Some(date) = parse_date(now.year(), token) {
+                    set_day(date, input)?;
+                } else {
+                    let (parsed, consumed) =
+                        parse_time_tokens(token, next).ok_or_else(|| unknown(token))?;
+                    if time.replace(parsed).is_some() {
+                        return Err(anyhow!(GcalError::Parse(format!(
+                            "The time is given more than once in '{}'",
+                            input
+                        ))));
+                    }
+                    i += consumed - 1;
+                }
+            }
+        }
+    }
+
+    match (day, time, offset) {
+        (None, None, Some(offset)) => {
+            let now = now.date().and_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now);
+            now.checked_add_signed(offset).ok_or_else(out_of_range)
+        }
+        (None, Some(time), Some(offset)) if offset.num_seconds() % 86_400 == 0 => {
+            let date = now.date().checked_add_signed(offset);
+            Ok(date.ok_or_else(out_of_range)?.and_time(time))
+        }
+        (_, _, Some(_)) => Err(anyhow!(GcalError::Parse(format!(
+            "Only an offset in whole days can be combined with a time, and none with a day, in '{}'",
+            input
+        )))),
+        (day, Some(time), None) => Ok(day.unwrap_or(now.date()).and_time(time)),
+        (_, None, None) => Err(anyhow!(GcalError::Parse(format!(
+            "Missing time in '{}', e.g. '{} 10:00'",
+            input,
+            input.trim()
+        )))),
+    }
+}
+
+/// Returns the next given weekday on or after `today`, or strictly after it with `after_today`.
+fn get_next_weekday(today: NaiveDate, weekday: Weekday, after_today: bool) -> NaiveDate {
+    let days = (weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    match days {
+        0 if after_today => today + Duration::days(7),
+        days => today + Duration::days(days),
+    }
+}
+
+/// Parses a `YYYY-MM-DD` or `MM-DD` date token, using `year` when the year is omitted.
+fn parse_date(year: i32, token: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(token, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}-{}", year, token), "%Y-%m-%d"))
+        .ok()
+}
+
+/// Parses a relative offset such as `2h`, `1h30m`, `3d` or `2 hours` from the start of `tokens`.
+///
+/// Returns the offset and the number of tokens it spans.
+fn parse_offset(tokens: &[&str]) -> Option<(Duration, usize)> {
+    let to_duration = |amount: i64, unit: &str| match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount),
+        "d" | "day" | "days" => Duration::try_days(amount),
+        "w" | "week" | "weeks" => Duration::try_weeks(amount),
+        _ => None,
+    };
+    let first = *tokens.first().filter(|first| !first.is_empty())?;
+    if let (Ok(amount), Some(unit)) = (first.parse::<i64>(), tokens.get(1)) {
+        return to_duration(amount, unit).map(|offset| (offset, 2));
+    }
+
+    let mut offset = Duration::zero();
+    let mut rest = first;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let units = rest[digits..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |end| digits + end);
+        let part = to_duration(rest[..digits].parse().ok()?, &rest[digits..units])?;
+        offset = offset.checked_add(&part)?;
+        rest = &rest[units..];
+    }
+    Some((offset, 1))
+}
+
+/// Parses a time such as `14:00`, `9am`, `9:30pm`, `noon` or `midnight`, accepting the
+/// `am`/`pm` suffix as a separate token too.
+///
+/// Returns the time and the number of tokens it spans.
+fn parse_time_tokens(token: &str, next: Option<&str>) -> Option<(NaiveTime, usize)> {
+    match token {
+        "noon" => return Some((NaiveTime::from_hms_opt(12, 0, 0)?, 1)),
+        "midnight" => return Some((NaiveTime::MIN, 1)),
+        _ => {}
+    }
+    if !matches!(next, Some("am" | "pm")) {
+        if let Ok(time) = NaiveTime::parse_from_str(token, "%H:%M") {
+            return Some((time, 1));
+        }
+    }
+    let (clock, meridiem, consumed) = match next {
+        Some(next @ ("am" | "pm")) => (token, next, 2),
+        _ => {
+            let split = token.len().checked_sub(2)?;
+            (token.get(..split)?, token.get(split..)?, 1)
+        }
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse().ok()?, minute.parse().ok()?),
+        Some(_) => return None,
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+    let hour = match meridiem {
+        "am" => hour % 12,
+        "pm" => hour % 12 + 12,
+        _ => return None,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0).map(|time| (time, consumed))
+}
+
+/// Parses a time of day on its own, e.g. `14:00`, `9am` or `9:30 pm`.
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    let lowercase = time.trim().to_lowercase();
+    let tokens: Vec<&str> = lowercase.split_whitespace().collect();
+    match parse_time_tokens(tokens.first()?, tokens.get(1).copied()) {
+        Some((time, consumed)) if consumed == tokens.len() => Some(time),
+        _ => None,
+    }
+}
+
+/// Converts a date string to a `NaiveDate`.
+///
+/// This function accepts a date string in the format `YYYY-MM-DD` or `MM-DD`. If the
+/// year is omitted, the current year is used.
+///
+/// # Arguments
+///
+/// * `date` - A string slice that holds the date to be parsed.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `NaiveDate`, or an error if the string does not
+/// match any of the supported formats.
+///
+/// # Examples
+///
+/// ```
+/// let date = get_naive_date_from_string("2024-07-27").unwrap();
+/// println!("{}", date); // 2024-07-27
+/// ```
+pub fn get_naive_date_from_string(date: &str) -> Result<NaiveDate> {
+    if let Ok(parsed_date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(parsed_date);
+    }
+    NaiveDate::parse_from_str(&format!("{}-{}", Utc::now().year(), date), "%Y-%m-%d").map_err(
+        |_| {
+            anyhow!(GcalError::Parse(format!(
+                "Invalid date '{}', expected YYYY-MM-DD or MM-DD",
+                date
+            )))
+        },
+    )
+}
+
+/// Computes the days covered by a listing from the range options given by the user.
+///
+/// Without any option, the current week is returned. `week` shifts that week
+/// by the given number of weeks, `days` sets the length of the range, `from` its first day
+/// and `to` its last day (inclusive). When only `from` is given, the range is one week long;
+/// when only `to` is given, the range starts today.
+///
+/// # Arguments
+///
+/// * `start_of_the_week` - The first day of the current week, see `get_start_of_the_week`.
+/// * `today` - The current date.
+/// * `from` - The first day of the range.
+/// * `to` - The last day of the range.
+/// * `week` - The week offset relative to the current week.
+/// * `days` - The number of days in the range.
+///
+/// # Returns
+///
+/// A `Result` containing the first day of the range and the day after its last day, or an
+/// error if the range is empty.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+///
+/// let start_of_the_week = NaiveDate::from_ymd_opt(2024, 7, 22).unwrap();
+/// let today = NaiveDate::from_ymd_opt(2024, 7, 24).unwrap();
+/// let (start, end) = get_date_range(start_of_the_week, today, None, None, Some(1), None).unwrap();
+/// println!("{} - {}", start, end); // 2024-07-29 - 2024-08-05
+/// ```
+pub fn get_date_range(
+    start_of_the_week: NaiveDate,
+    today: NaiveDate,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    week: Option<i64>,
+    days: Option<i64>,
+) -> Result<(NaiveDate, NaiveDate)> {
+    let shift = |date: NaiveDate, delta: Option<Duration>| {
+        delta
+            .and_then(|delta| date.checked_add_signed(delta))
+            .ok_or_else(|| anyhow!(GcalError::Parse("The range is out of bounds".to_string())))
+    };
+    let start = match (from, to, week, days) {
+        (Some(from), _, _, _) => from,
+        (None, None, None, None) => start_of_the_week,
+        (None, None, Some(week), _) => shift(start_of_the_week, Duration::try_weeks(week))?,
+        _ => today,
+    };
+    let end = match (to, days) {
+        (Some(to), _) => shift(to, Duration::try_days(1))?,
+        (None, Some(days)) => shift(start, Duration::try_days(days))?,
+        (None, None) => shift(start, Duration::try_days(7))?,
+    };
+    if end <= start {
+        return Err(anyhow!(GcalError::Parse(
+            "The end of the range must not be before its start".to_string()
+        )));
+    }
+    Ok((start, end))
+}
+
+/// Returns the first day of the month of the given date and the first day of the next month.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+///
+/// let (start, end) = get_month_range(NaiveDate::from_ymd_opt(2024, 12, 24).unwrap());
+/// println!("{} - {}", start, end); // 2024-12-01 - 2025-01-01
+/// ```
+pub fn get_month_range(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = date.with_day(1).unwrap_or(date);
+    let end = start
+        .checked_add_months(Months::new(1))
+        .unwrap_or(start + Duration::days(31));
+    (start, end)
+}
+
+/// Returns the UTC bounds of the given day in the provided timezone.
+///
+/// The start is midnight of the given day and the end is midnight of the following day,
+/// both interpreted in `tz`.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use chrono_tz::Tz;
+///
+/// let tz: Tz = "Europe/Budapest".parse().unwrap();
+/// let (start, end) = get_day_bounds(tz, NaiveDate::from_ymd_opt(2024, 7, 27).unwrap());
+/// println!("{} - {}", start, end); // 2024-07-26 22:00:00 UTC - 2024-07-27 22:00:00 UTC
+/// ```
+pub fn get_day_bounds(tz: Tz, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let to_utc = |day: NaiveDate| {
+        let midnight = day.and_time(NaiveTime::MIN);
+        tz.from_local_datetime(&midnight)
+            .earliest()
+            .map(|date_time| date_time.to_utc())
+            .unwrap_or_else(|| midnight.and_utc())
+    };
+    (to_utc(date), to_utc(date + Duration::days(1)))
+}
+
+/// Parses an event duration given as hours and minutes, e.g. `30m`, `1h30m` or `2h`, or as a
+/// plain number of minutes, e.g. `90`.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `Duration`, or an error if the string is not a valid,
+/// positive duration.
+///
+/// # Examples
+///
+/// ```
+/// let duration = parse_duration("1h30m").unwrap();
+/// println!("{}", duration.num_minutes()); // 90
+/// ```
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let invalid = || {
+        anyhow!(GcalError::Parse(format!(
+            "Invalid duration '{}', expected e.g. 30m, 1h30m or 90",
+            duration
+        )))
+    };
+    let too_long = || {
+        anyhow!(GcalError::Parse(format!(
+            "The duration '{}' is too long",
+            duration
+        )))
+    };
+    let value = duration.trim().to_lowercase();
+    if let Ok(minutes) = value.parse::<i64>() {
+        let parsed = Duration::try_minutes(minutes).ok_or_else(too_long)?;
+        return Some(parsed)
+            .filter(|parsed| *parsed > Duration::zero())
+            .ok_or_else(invalid);
+    }
+
+    let mut parsed = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let amount: i64 = number.parse().map_err(|_| too_long())?;
+                let part = if c == 'h' {
+                    Duration::try_hours(amount)
+                } else {
+                    Duration::try_minutes(amount)
+                };
+                parsed = part
+                    .and_then(|part| parsed.checked_add(&part))
+                    .ok_or_else(too_long)?;
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || parsed <= Duration::zero() {
+        return Err(invalid());
+    }
+    Ok(parsed)
+}
+
+/// Splits a date argument containing a time range, e.g. `10:00-10:25`, `9am-10:30am` or
+/// `2024-07-27 14:00-15:30`, into the start and the end time.
+///
+/// The dashes of the date itself are left alone, since only a trailing time is treated as
+/// the end of the range.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(split_time_range("2024-07-27 14:00-15:30"), ("2024-07-27 14:00", Some("15:30")));
+/// assert_eq!(split_time_range("2024-07-27 14:00"), ("2024-07-27 14:00", None));
+/// ```
+pub fn split_time_range(date: &str) -> (&str, Option<&str>) {
+    match date.rsplit_once('-') {
+        Some((start, end)) if !start.trim().is_empty() && parse_time(end).is_some() => {
+            (start.trim(), Some(end.trim()))
+        }
+        _ => (date, None),
+    }
+}
+
+/// Converts the end of an event to a `DateTime<Utc>`.
+///
+/// The end is either a time such as `15:30` or `4pm`, taken on the day the event starts, or
+/// any date accepted by `get_date_from_string`, both interpreted in `tz`.
+///
+/// # Arguments
+///
+/// * `tz` - A timezone from the `chrono_tz` crate.
+/// * `start` - The start of the event.
+/// * `end` - A string slice that holds the end to be parsed.
+///
+/// # Returns
+///
+/// A `Result` containing the end in UTC, or an error if the string cannot be parsed or the
+/// end is not after the start.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// let tz: Tz = "Europe/Budapest".parse().unwrap();
+/// let start = Utc.with_ymd_and_hms(2024, 7, 27, 8, 0, 0).unwrap();
+/// let end = get_end_date(tz, start, "10:25").unwrap();
+/// println!("{}", end); // 2024-07-27 08:25:00 UTC
+/// ```
+pub fn get_end_date(tz: Tz, start: DateTime<Utc>, end: &str) -> Result<DateTime<Utc>> {
+    let end_date = match parse_time(end) {
+        Some(time) => {
+            let local_end = start.with_timezone(&tz).date_naive().and_time(time);
+            tz.from_local_datetime(&local_end)
+                .earliest()
+                .ok_or_else(|| {
+                    anyhow!(GcalError::Parse(format!(
+                        "The end '{}' does not exist in {}",
+                        end,
+                        tz.name()
+                    )))
+                })?
+                .to_utc()
+        }
+        None => get_date_from_string(tz, end)?,
+    };
+    if end_date <= start {
+        return Err(anyhow!(GcalError::Parse(
+            "The end of the event must be after its start".to_string()
+        )));
+    }
+    Ok(end_date)
+}
+
+/// Parses a range of times within a day, e.g. `09:00-17:00` or `9am-5pm`.
+///
+/// # Returns
+///
+/// A `Result` containing the start and end times, or an error if either time is invalid or
+/// the range does not end after it starts.
+///
+/// # Examples
+///
+/// ```
+/// let (start, end) = parse_time_range("9am-17:30").unwrap();
+/// println!("{} - {}", start, end); // 09:00:00 - 17:30:00
+/// ```
+pub fn parse_time_range(range: &str) -> Result<(NaiveTime, NaiveTime)> {
+    let invalid = || {
+        GcalError::Parse(format!(
+            "Invalid time range '{}', expected e.g. 09:00-17:00",
+            range
+        ))
+    };
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let start = parse_time(start).ok_or_else(invalid)?;
+    let end = parse_time(end).ok_or_else(invalid)?;
+    if end <= start {
+        return Err(anyhow!(invalid()));
     }
+    Ok((start, end))
+}
+
+/// Parses a period of days relative to `today`.
+///
+/// Supported periods are `today`, `tomorrow`, `this week` (the rest of the current week),
+/// `next week`, `this month` (the rest of the current month), `next month`, `next N days`
+/// and single dates in the `YYYY-MM-DD` or `MM-DD` format. Weeks start on `week_start`.
+///
+/// # Returns
+///
+/// A `Result` containing the first day of the period and the day after its last day, or an
+/// error if the period is not understood.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+///
+/// let today = NaiveDate::from_ymd_opt(2024, 7, 24).unwrap();
+/// let (start, end) = parse_period(today, Weekday::Mon, "next week").unwrap();
+/// println!("{} - {}", start, end); // 2024-07-29 - 2024-08-05
+/// ```
+pub fn parse_period(
+    today: NaiveDate,
+    week_start: Weekday,
+    period: &str,
+) -> Result<(NaiveDate, NaiveDate)> {
+    let next_week = today + Duration::days(7 - today.weekday().days_since(week_start) as i64);
+    let text = period.trim().to_lowercase();
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let range = match tokens.as_slice() {
+        ["today"] => (today, today + Duration::days(1)),
+        ["tomorrow"] => (today + Duration::days(1), today + Duration::days(2)),
+        ["this", "week"] => (today, next_week),
+        ["next", "week"] => (next_week, next_week + Duration::days(7)),
+        ["this", "month"] => (today, get_month_range(today).1),
+        ["next", "month"] => get_month_range(get_month_range(today).1),
+        ["next", days, "days" | "day"] => {
+            let end = days
+                .parse::<i64>()
+                .ok()
+                .filter(|days| *days > 0)
+                .and_then(Duration::try_days)
+                .and_then(|days| today.checked_add_signed(days));
+            match end {
+                Some(end) => (today, end),
+                None => return Err(anyhow!(invalid_period(period))),
+            }
+        }
+        [date] => match parse_date(today.year(), date) {
+            Some(date) => match date.succ_opt() {
+                Some(next_day) => (date, next_day),
+                None => return Err(anyhow!(invalid_period(period))),
+            },
+            None => return Err(anyhow!(invalid_period(period))),
+        },
+        _ => return Err(anyhow!(invalid_period(period))),
+    };
+    Ok(range)
+}
+
+fn invalid_period(period: &str) -> GcalError {
+    GcalError::Parse(format!(
+        "Invalid period '{}', expected e.g. today, tomorrow, this week, next week, next 3 days or 2024-07-27",
+        period
+    ))
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::{TimeZone, Weekday};
+    use chrono::TimeZone;
 
     use super::*;
 
@@ -131,8 +700,8 @@ mod tests {
         let tz: Tz = "America/New_York".parse().unwrap(); // UTC is 4 hours ahead of New York
         let date = String::from("2024-07-27 15:30");
 
-        let actual_date = get_date_from_string(tz, &date);
-        let expected_date = Utc.with_ymd_and_hms(2024, 07, 27, 19, 30, 0).unwrap();
+        let actual_date = get_date_from_string(tz, &date).unwrap();
+        let expected_date = Utc.with_ymd_and_hms(2024, 7, 27, 19, 30, 0).unwrap();
 
         assert_eq!(actual_date, expected_date);
         Ok(())
@@ -144,9 +713,9 @@ mod tests {
         let date = String::from("06-11 0:30");
         let current_year = Utc::now().year();
 
-        let actual_date = get_date_from_string(tz, &date);
+        let actual_date = get_date_from_string(tz, &date).unwrap();
         let expected_date = Utc
-            .with_ymd_and_hms(current_year, 06, 10, 22, 30, 0)
+            .with_ymd_and_hms(current_year, 6, 10, 22, 30, 0)
             .unwrap();
 
         assert_eq!(actual_date, expected_date);
@@ -157,9 +726,9 @@ mod tests {
     fn test_extracting_only_hours_and_minutes_from_string() -> Result<(), String> {
         let tz: Tz = "Asia/Tokyo".parse().unwrap(); // UTC is 9 hours behind Tokyo
         let date = String::from("23:12");
-        let now = Utc::now();
+        let now = Utc::now().with_timezone(&tz);
 
-        let actual_date = get_date_from_string(tz, &date);
+        let actual_date = get_date_from_string(tz, &date).unwrap();
         let expected_date = Utc
             .with_ymd_and_hms(now.year(), now.month(), now.day(), 14, 12, 0)
             .unwrap();
@@ -168,16 +737,323 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parsing_natural_language_dates() -> Result<(), String> {
+        // Wednesday
+        let now = NaiveDate::from_ymd_opt(2024, 7, 24)
+            .unwrap()
+            .and_hms_opt(10, 17, 42)
+            .unwrap();
+        let cases = [
+            ("14:00", "2024-07-24 14:00"),
+            ("0:30", "2024-07-24 00:30"),
+            ("2024-08-02 09:15", "2024-08-02 09:15"),
+            ("08-02 09:15", "2024-08-02 09:15"),
+            ("today 18:00", "2024-07-24 18:00"),
+            ("Tomorrow 10:00", "2024-07-25 10:00"),
+            ("tmrw at 10:00", "2024-07-25 10:00"),
+            ("yesterday noon", "2024-07-23 12:00"),
+            ("10:00 tomorrow", "2024-07-25 10:00"),
+            ("fri 9am", "2024-07-26 09:00"),
+            ("friday 9:30 pm", "2024-07-26 21:30"),
+            ("wed 16:00", "2024-07-24 16:00"),
+            ("next wed 16:00", "2024-07-31 16:00"),
+            ("next fri 9am", "2024-07-26 09:00"),
+            ("this monday 8am", "2024-07-29 08:00"),
+            ("on mon at 11:30am", "2024-07-29 11:30"),
+            ("in 2h", "2024-07-24 12:17"),
+            ("in 30 minutes", "2024-07-24 10:47"),
+            ("in 1h30m", "2024-07-24 11:47"),
+            ("+90m", "2024-07-24 11:47"),
+            ("+3d 14:00", "2024-07-27 14:00"),
+            ("+1w 9am", "2024-07-31 09:00"),
+            ("+2 days noon", "2024-07-26 12:00"),
+            ("noon", "2024-07-24 12:00"),
+            ("midnight", "2024-07-24 00:00"),
+            ("12am", "2024-07-24 00:00"),
+            ("12pm", "2024-07-24 12:00"),
+            ("12:45pm", "2024-07-24 12:45"),
+            ("7 PM", "2024-07-24 19:00"),
+        ];
+        for (input, expected) in cases {
+            let actual = parse_local_date_time(now, input)
+                .unwrap_or_else(|e| panic!("failed to parse '{}': {}", input, e));
+            assert_eq!(
+                actual.format("%Y-%m-%d %H:%M").to_string(),
+                expected,
+                "input: {}",
+                input
+            );
+        }
+
+        let invalid = [
+            "",
+            "tomorrow",
+            "next week 10:00",
+            "13pm",
+            "0am",
+            "9:5pm",
+            "someday 10:00",
+            "tomorrow today 10:00",
+            "10:00 11:00",
+            "in 2h 14:00",
+            "tomorrow in 2h",
+            "in",
+            "+",
+            "+3x",
+            "é9",
+            "in 99999999999999 d",
+            "in 99999999999999d",
+            "+9999999999w",
+            "+9999999999 weeks",
+            "in 9999999999w +9999999999w",
+            "+20000000w 10:00",
+        ];
+        for input in invalid {
+            assert!(
+                parse_local_date_time(now, input).is_err(),
+                "input: {}",
+                input
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extracting_date_in_dst_gap_from_string() -> Result<(), String> {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+
+        assert!(get_date_from_string(tz, "2024-03-31 02:30").is_err());
+        assert_eq!(
+            get_date_from_string(tz, "2024-10-27 02:30").unwrap(),
+            Utc.with_ymd_and_hms(2024, 10, 27, 0, 30, 0).unwrap()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_get_start_of_the_week() -> Result<(), String> {
-        let now = Local::now();
+        for week_start in [Weekday::Mon, Weekday::Sun] {
+            let start_of_the_week = get_start_of_the_week(week_start);
+            let now = Local::now();
 
-        let start_of_the_week = get_start_of_the_week();
+            let days_difference = now.signed_duration_since(start_of_the_week).num_days();
+            assert!(start_of_the_week <= now);
+            assert!((0..=6).contains(&days_difference));
+            assert_eq!(start_of_the_week.weekday(), week_start);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extracting_naive_date_from_string() -> Result<(), String> {
+        let current_year = Utc::now().year();
+
+        assert_eq!(
+            get_naive_date_from_string("2024-07-27").ok(),
+            NaiveDate::from_ymd_opt(2024, 7, 27)
+        );
+        assert_eq!(
+            get_naive_date_from_string("07-27").ok(),
+            NaiveDate::from_ymd_opt(current_year, 7, 27)
+        );
+        assert!(get_naive_date_from_string("next week").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_date_range() -> Result<(), String> {
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        let start_of_the_week = date(7, 22);
+        let today = date(7, 24);
+
+        let cases = [
+            ((None, None, None, None), (date(7, 22), date(7, 29))),
+            ((None, None, Some(1), None), (date(7, 29), date(8, 5))),
+            ((None, None, Some(-1), None), (date(7, 15), date(7, 22))),
+            ((None, None, None, Some(3)), (date(7, 24), date(7, 27))),
+            (
+                (Some(date(7, 1)), None, None, None),
+                (date(7, 1), date(7, 8)),
+            ),
+            (
+                (Some(date(7, 1)), Some(date(7, 31)), None, None),
+                (date(7, 1), date(8, 1)),
+            ),
+            (
+                (Some(date(7, 1)), None, None, Some(2)),
+                (date(7, 1), date(7, 3)),
+            ),
+            (
+                (None, Some(date(7, 26)), None, None),
+                (date(7, 24), date(7, 27)),
+            ),
+        ];
+        for ((from, to, week, days), expected) in cases {
+            assert_eq!(
+                get_date_range(start_of_the_week, today, from, to, week, days).ok(),
+                Some(expected),
+                "from: {:?}, to: {:?}, week: {:?}, days: {:?}",
+                from,
+                to,
+                week,
+                days
+            );
+        }
+        assert!(get_date_range(
+            start_of_the_week,
+            today,
+            Some(date(7, 2)),
+            Some(date(7, 1)),
+            None,
+            None
+        )
+        .is_err());
+        assert!(get_date_range(start_of_the_week, today, None, None, None, Some(0)).is_err());
+        for (week, days) in [
+            (Some(i64::MAX), None),
+            (Some(99999999999999), None),
+            (None, Some(99999999999999)),
+        ] {
+            assert!(get_date_range(start_of_the_week, today, None, None, week, days).is_err());
+        }
+        Ok(())
+    }
 
-        let days_difference = now.signed_duration_since(start_of_the_week).num_days();        
-        assert!(start_of_the_week <= now);
-        assert!(days_difference >= 0 && days_difference <= 6);
-        assert_eq!(start_of_the_week.weekday(), Weekday::Mon);
+    #[test]
+    fn test_get_month_range() -> Result<(), String> {
+        let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+
+        assert_eq!(
+            get_month_range(date(2024, 2, 15)),
+            (date(2024, 2, 1), date(2024, 3, 1))
+        );
+        assert_eq!(
+            get_month_range(date(2024, 12, 31)),
+            (date(2024, 12, 1), date(2025, 1, 1))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_day_bounds() -> Result<(), String> {
+        let tz: Tz = "Europe/Budapest".parse().unwrap(); // UTC is 2 hours behind Budapest (CEST)
+        let date = NaiveDate::from_ymd_opt(2024, 7, 27).unwrap();
+
+        let (start, end) = get_day_bounds(tz, date);
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 7, 26, 22, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2024, 7, 27, 22, 0, 0).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_duration() -> Result<(), String> {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("2H").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration("90").unwrap(), Duration::minutes(90));
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("999999999999999999").is_err());
+        assert!(parse_duration("99999999999999h").is_err());
+        assert!(parse_duration("99999999999999999999m").is_err());
+        assert!(parse_duration("2562047788015h2562047788015h").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_time_range() -> Result<(), String> {
+        assert_eq!(split_time_range("10:00-10:25"), ("10:00", Some("10:25")));
+        assert_eq!(
+            split_time_range("2026-11-02 14:00 - 15:30"),
+            ("2026-11-02 14:00", Some("15:30"))
+        );
+        assert_eq!(
+            split_time_range("2026-11-02 14:00"),
+            ("2026-11-02 14:00", None)
+        );
+        assert_eq!(split_time_range("07-13 23:25"), ("07-13 23:25", None));
+        assert_eq!(
+            split_time_range("fri 9am-10:30am"),
+            ("fri 9am", Some("10:30am"))
+        );
+        assert_eq!(split_time_range("11-03"), ("11-03", None));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_end_date() -> Result<(), String> {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 7, 27, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            get_end_date(tz, start, "15:30").unwrap(),
+            Utc.with_ymd_and_hms(2024, 7, 27, 13, 30, 0).unwrap()
+        );
+        assert_eq!(
+            get_end_date(tz, start, "2024-07-28 09:00").unwrap(),
+            Utc.with_ymd_and_hms(2024, 7, 28, 7, 0, 0).unwrap()
+        );
+        assert!(get_end_date(tz, start, "13:00").is_err());
+        assert_eq!(
+            get_end_date(tz, start, "4pm").unwrap(),
+            Utc.with_ymd_and_hms(2024, 7, 27, 14, 0, 0).unwrap()
+        );
+        assert!(get_end_date(tz, start, "later").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_time_range() -> Result<(), String> {
+        let time = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+
+        assert_eq!(
+            parse_time_range("09:00-17:00").unwrap(),
+            (time(9, 0), time(17, 0))
+        );
+        assert_eq!(
+            parse_time_range("8:30am - 4pm").unwrap(),
+            (time(8, 30), time(16, 0))
+        );
+        assert!(parse_time_range("17:00-09:00").is_err());
+        assert!(parse_time_range("09:00").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_period() -> Result<(), String> {
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        // Wednesday
+        let today = date(7, 24);
+        let cases = [
+            ("today", (date(7, 24), date(7, 25))),
+            ("Tomorrow", (date(7, 25), date(7, 26))),
+            ("this week", (date(7, 24), date(7, 29))),
+            ("next week", (date(7, 29), date(8, 5))),
+            ("this month", (date(7, 24), date(8, 1))),
+            ("next month", (date(8, 1), date(9, 1))),
+            ("next 3 days", (date(7, 24), date(7, 27))),
+            ("08-02", (date(8, 2), date(8, 3))),
+        ];
+        for (period, expected) in cases {
+            assert_eq!(
+                parse_period(today, Weekday::Mon, period).unwrap(),
+                expected,
+                "{}",
+                period
+            );
+        }
+        assert_eq!(
+            parse_period(today, Weekday::Sun, "next week").unwrap(),
+            (date(7, 28), date(8, 4))
+        );
+        assert!(parse_period(today, Weekday::Mon, "next 0 days").is_err());
+        assert!(parse_period(today, Weekday::Mon, "next 100000000 days").is_err());
+        assert!(parse_period(today, Weekday::Mon, "next 9223372036854775807 days").is_err());
+        assert!(parse_period(today, Weekday::Mon, "someday").is_err());
         Ok(())
     }
 }
@@ -0,0 +1,211 @@
+use std::fmt;
+
+use google_calendar3::common;
+
+/// Exit code for failures which do not fall into any of the categories below.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid command line input, matching the code used by `clap` for usage errors.
+pub const EXIT_PARSE: i32 = 2;
+/// Exit code for missing or unreadable local files, such as the secret or the token store.
+pub const EXIT_CONFIG: i32 = 3;
+/// Exit code for authentication failures.
+pub const EXIT_AUTH: i32 = 4;
+/// Exit code for failures to reach the Google Calendar API.
+pub const EXIT_NETWORK: i32 = 5;
+/// Exit code for requests rejected by the Google Calendar API.
+pub const EXIT_API: i32 = 6;
+/// Exit code for events or calendars which could not be found.
+pub const EXIT_NOT_FOUND: i32 = 7;
+/// Exit code for events refused because they overlap with existing events.
+pub const EXIT_CONFLICT: i32 = 8;
+
+/// The categories of failures reported by the CLI, each with its own exit code.
+#[derive(Debug)]
+pub enum GcalError {
+    /// The user could not be authenticated with Google.
+    Auth(String),
+    /// The Google Calendar API could not be reached.
+    Network(String),
+    /// The Google Calendar API rejected the request.
+    Api {
+        status: Option<u16>,
+        message: String,
+    },
+    /// A value given on the command line could not be parsed or is invalid.
+    Parse(String),
+    /// A local file could not be read or written.
+    Config(String),
+    /// The requested event or calendar does not exist.
+    NotFound(String),
+    /// The event overlaps with existing events.
+    Conflict(String),
+}
+
+impl GcalError {
+    /// Returns the process exit code of this kind of failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GcalError::Auth(_) => EXIT_AUTH,
+            GcalError::Network(_) => EXIT_NETWORK,
+            GcalError::Api {
+                status: Some(404), ..
+            } => EXIT_NOT_FOUND,
+            GcalError::Api {
+                status: Some(401), ..
+            } => EXIT_AUTH,
+            GcalError::Api { .. } => EXIT_API,
+            GcalError::Parse(_) => EXIT_PARSE,
+            GcalError::Config(_) => EXIT_CONFIG,
+            GcalError::NotFound(_) => EXIT_NOT_FOUND,
+            GcalError::Conflict(_) => EXIT_CONFLICT,
+        }
+    }
+}
+
+impl fmt::Display for GcalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GcalError::Auth(message) => write!(f, "Authentication failed: {}", message),
+            GcalError::Network(message) => {
+                write!(f, "Could not reach Google Calendar: {}", message)
+            }
+            GcalError::Api {
+                status: Some(status),
+                message,
+            } => write!(f, "Google Calendar error {}: {}", status, message),
+            GcalError::Api {
+                status: None,
+                message,
+            } => write!(f, "Google Calendar error: {}", message),
+            GcalError::Parse(message)
+            | GcalError::Config(message)
+            | GcalError::NotFound(message)
+            | GcalError::Conflict(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GcalError {}
+
+impl From<&common::Error> for GcalError {
+    fn from(error: &common::Error) -> GcalError {
+        match error {
+            common::Error::HttpError(e) => GcalError::Network(e.to_string()),
+            common::Error::Io(e) => GcalError::Network(e.to_string()),
+            common::Error::MissingToken(e) => GcalError::Auth(e.to_string()),
+            common::Error::MissingAPIKey => GcalError::Auth("No API key available".to_string()),
+            common::Error::BadRequest(value) => {
+                let error = &value["error"];
+                GcalError::Api {
+                    status: error["code"].as_u64().map(|code| code as u16),
+                    message: error["message"]
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string),
+                }
+            }
+            common::Error::Failure(response) => GcalError::Api {
+                status: Some(response.status().as_u16()),
+                message: response
+                    .status()
+                    .canonical_reason()
+                    .unwrap_or("Request failed")
+                    .to_string(),
+            },
+            e => GcalError::Api {
+                status: None,
+                message: e.to_string().trim().to_string(),
+            },
+        }
+    }
+}
+
+/// Returns the exit code of an error, taken from the first typed error in its chain.
+///
+/// ## Arguments
+///
+/// * `error` - The error returned by a command.
+///
+/// ## Returns
+///
+/// * `i32` - One of the `EXIT_*` codes, `EXIT_FAILURE` if the error is not categorized.
+pub fn get_exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .chain()
+        .find_map(|cause| {
+            if let Some(error) = cause.downcast_ref::<GcalError>() {
+                Some(error.exit_code())
+            } else {
+                cause
+                    .downcast_ref::<common::Error>()
+                    .map(|error| GcalError::from(error).exit_code())
+            }
+        })
+        .unwrap_or(EXIT_FAILURE)
+}
+
+/// Formats an error with its context for the user, replacing raw API errors by their message.
+///
+/// ## Arguments
+///
+/// * `error` - The error returned by a command.
+///
+/// ## Returns
+///
+/// * `String` - The messages of the error chain, separated by colons.
+pub fn format_error(error: &anyhow::Error) -> String {
+    let mut messages = vec![];
+    for cause in error.chain() {
+        if let Some(api_error) = cause.downcast_ref::<common::Error>() {
+            messages.push(GcalError::from(api_error).to_string());
+            break;
+        }
+        messages.push(cause.to_string().trim().to_string());
+    }
+    messages.join(": ")
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_get_exit_code() {
+        let parse_error = anyhow!(GcalError::Parse("Invalid date".to_string()));
+        let api_error = Err::<(), _>(common::Error::BadRequest(json!({
+            "error": { "code": 404, "message": "Not Found" }
+        })))
+        .context("Failed to get event")
+        .unwrap_err();
+
+        assert_eq!(get_exit_code(&parse_error), EXIT_PARSE);
+        assert_eq!(get_exit_code(&api_error), EXIT_NOT_FOUND);
+        assert_eq!(get_exit_code(&anyhow!("Cancelled")), EXIT_FAILURE);
+        assert_eq!(
+            get_exit_code(&anyhow!(common::Error::MissingAPIKey)),
+            EXIT_AUTH
+        );
+    }
+
+    #[test]
+    fn test_format_error() {
+        let api_error = Err::<(), _>(common::Error::BadRequest(json!({
+            "error": { "code": 400, "message": "Invalid start time." }
+        })))
+        .context("Failed to create event")
+        .unwrap_err();
+
+        assert_eq!(
+            format_error(&api_error),
+            "Failed to create event: Google Calendar error 400: Invalid start time."
+        );
+        assert_eq!(
+            format_error(&anyhow!(GcalError::NotFound(
+                "Calendar 'Team' not found".to_string()
+            ))),
+            "Calendar 'Team' not found"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use google_calendar3::api::{ConferenceData, ConferenceSolutionKey, CreateConferenceRequest};
+use google_calendar3::api::{Event, EventDateTime};
+use uuid::Uuid;
+
+use super::attendee::is_declined;
+
+/// Returns `true` if the event is an instance of, or the parent of, a recurring series.
+pub fn is_recurring(event: &Event) -> bool {
+    event.recurring_event_id.is_some() || event.recurrence.is_some()
+}
+
+/// Returns the start of an event in UTC.
+///
+/// All-day events start at midnight of their first day in the given timezone.
+pub fn get_event_start(event: &Event, tz: Tz) -> Option<DateTime<Utc>> {
+    get_date_time(event.start.as_ref()?, tz)
+}
+
+/// Returns `true` if the event blocks some of the time between `start` and `end`.
+///
+/// All-day events, cancelled events, events marked as free and invitations the current user
+/// declined do not block any time.
+pub fn blocks_time(event: &Event, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+    let (Some(event_start), Some(event_end)) = (
+        event.start.as_ref().and_then(|start| start.date_time),
+        event.end.as_ref().and_then(|end| end.date_time),
+    ) else {
+        return false;
+    };
+    event_start < end
+        && event_end > start
+        && event.status.as_deref() != Some("cancelled")
+        && event.transparency.as_deref() != Some("transparent")
+        && !is_declined(event)
+}
+
+/// Returns the days covered by an all-day or multi-day event, in the given timezone.
+///
+/// Timed events starting and ending on the same day return `None`. A timed event ending
+/// exactly at midnight does not cover the day it ends on.
+///
+/// ## Arguments
+///
+/// * `event` - The event to check.
+/// * `tz` - The timezone in which the days are computed.
+///
+/// ## Returns
+///
+/// * `Option<Vec<NaiveDate>>` - The covered days, in order, or `None` for single-day timed events.
+pub fn get_all_day_dates(event: &Event, tz: Tz) -> Option<Vec<NaiveDate>> {
+    let start = event.start.as_ref()?;
+    let (first_day, last_day) = match start.date {
+        Some(first_day) => {
+            let end = event.end.as_ref().and_then(|end| end.date);
+            let last_day = end.map_or(first_day, |end| end - Duration::days(1));
+            (first_day, last_day.max(first_day))
+        }
+        None => {
+            let start = start.date_time?.with_timezone(&tz);
+            let end = event.end.as_ref()?.date_time?.with_timezone(&tz);
+            let last_day = (end - Duration::seconds(1)).date_naive();
+            if last_day <= start.date_naive() {
+                return None;
+            }
+            (start.date_naive(), last_day)
+        }
+    };
+    Some(
+        first_day
+            .iter_days()
+            .take_while(|day| *day <= last_day)
+            .collect(),
+    )
+}
+
+fn get_date_time(date_time: &EventDateTime, tz: Tz) -> Option<DateTime<Utc>> {
+    match date_time {
+        EventDateTime {
+            date_time: Some(date_time),
+            ..
+        } => Some(*date_time),
+        EventDateTime {
+            date: Some(date), ..
+        } => tz
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|date_time| date_time.to_utc()),
+        _ => None,
+    }
+}
+
+/// Formats the start and end of an event in the given timezone.
+///
+/// Timed events are rendered as `YYYY-MM-DD HH:MM - HH:MM`, all-day events as `YYYY-MM-DD (all day)`.
+///
+/// ## Arguments
+///
+/// * `event` - The event to format.
+/// * `tz` - The timezone in which the times are displayed.
+///
+/// ## Returns
+///
+/// * `String` - The formatted time range, or an empty string if the event has no start.
+pub fn format_event_time(event: &Event, tz: Tz) -> String {
+    let format_end = |end: Option<&EventDateTime>| {
+        end.and_then(|end| end.date_time)
+            .map(|end| format!(" - {}", end.with_timezone(&tz).format("%H:%M")))
+            .unwrap_or_default()
+    };
+    match event.start.as_ref() {
+        Some(EventDateTime {
+            date_time: Some(start),
+            ..
+        }) => format!(
+            "{}{}",
+            start.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+            format_end(event.end.as_ref())
+        ),
+        Some(EventDateTime {
+            date: Some(date), ..
+        }) => format!("{} (all day)", date),
+        _ => String::new(),
+    }
+}
+
+/// Formats an event as a single line with its time, title and ID.
+///
+/// ## Arguments
+///
+/// * `event` - The event to format.
+/// * `tz` - The timezone in which the times are displayed.
+///
+/// ## Returns
+///
+/// * `String` - A line like `2024-07-27 10:00 - 10:30  Standup  [abc123]`.
+pub fn format_event_line(event: &Event, tz: Tz) -> String {
+    format!(
+        "{}  {}  [{}]",
+        format_event_time(event, tz),
+        event.summary.as_deref().unwrap_or("(no title)"),
+        event.id.as_deref().unwrap_or_default()
+    )
+}
+
+/// Returns the video conference link of an event, if it has one.
+///
+/// The Google Meet `hangout_link` is preferred, otherwise the first `video` entry point of the
+/// conference data is used.
+pub fn get_conference_link(event: &Event) -> Option<String> {
+    event.hangout_link.clone().or_else(|| {
+        event
+            .conference_data
+            .as_ref()?
+            .entry_points
+            .as_ref()?
+            .iter()
+            .find(|entry_point| entry_point.entry_point_type.as_deref() == Some("video"))
+            .and_then(|entry_point| entry_point.uri.clone())
+    })
+}
+
+/// Returns the conference data which makes Google Calendar create a Meet conference for a
+/// new event, see `conference_data_version`.
+pub fn new_conference_data() -> ConferenceData {
+    ConferenceData {
+        create_request: Some(CreateConferenceRequest {
+            request_id: Some(Uuid::new_v4().to_string()),
+            conference_solution_key: Some(ConferenceSolutionKey {
+                type_: Some("hangoutsMeet".to_string()),
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_calendar3::api::EventAttendee;
+
+    use super::*;
+
+    fn event(start: EventDateTime, end: EventDateTime) -> Event {
+        Event {
+            start: Some(start),
+            end: Some(end),
+            ..Default::default()
+        }
+    }
+
+    fn date(day: u32) -> EventDateTime {
+        EventDateTime {
+            date: NaiveDate::from_ymd_opt(2024, 7, day),
+            ..Default::default()
+        }
+    }
+
+    fn date_time(day: u32, hour: u32) -> EventDateTime {
+        EventDateTime {
+            date_time: Some(Utc.with_ymd_and_hms(2024, 7, day, hour, 0, 0).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_blocks_time() {
+        let start = Utc.with_ymd_and_hms(2024, 7, 29, 10, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 7, 29, 11, 0, 0).unwrap();
+
+        assert!(blocks_time(
+            &event(date_time(29, 9), date_time(29, 12)),
+            start,
+            end
+        ));
+        assert!(!blocks_time(
+            &event(date_time(29, 9), date_time(29, 10)),
+            start,
+            end
+        ));
+        assert!(!blocks_time(
+            &event(date_time(29, 11), date_time(29, 12)),
+            start,
+            end
+        ));
+        assert!(!blocks_time(&event(date(29), date(30)), start, end));
+
+        let free = Event {
+            transparency: Some("transparent".to_string()),
+            ..event(date_time(29, 9), date_time(29, 12))
+        };
+        assert!(!blocks_time(&free, start, end));
+
+        let declined = Event {
+            attendees: Some(vec![EventAttendee {
+                self_: Some(true),
+                response_status: Some("declined".to_string()),
+                ..Default::default()
+            }]),
+            ..event(date_time(29, 9), date_time(29, 12))
+        };
+        assert!(!blocks_time(&declined, start, end));
+    }
+
+    #[test]
+    fn test_get_all_day_dates() {
+        let tz: Tz = "UTC".parse().unwrap();
+        let day = |day| NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+
+        assert_eq!(
+            get_all_day_dates(&event(date(27), date(28)), tz),
+            Some(vec![day(27)])
+        );
+        assert_eq!(
+            get_all_day_dates(&event(date(29), date(31)), tz),
+            Some(vec![day(29), day(30)])
+        );
+        assert_eq!(
+            get_all_day_dates(&event(date_time(29, 9), date_time(31, 17)), tz),
+            Some(vec![day(29), day(30), day(31)])
+        );
+        assert_eq!(
+            get_all_day_dates(&event(date_time(29, 22), date_time(30, 0)), tz),
+            None
+        );
+        assert_eq!(
+            get_all_day_dates(&event(date_time(29, 9), date_time(29, 10)), tz),
+            None
+        );
+    }
+}
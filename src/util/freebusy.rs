@@ -0,0 +1,160 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// A period of time, from its start (inclusive) to its end (exclusive).
+pub type Interval = (DateTime<Utc>, DateTime<Utc>);
+
+/// The constraints of a search for free time slots.
+pub struct SlotSearch {
+    /// The first day searched.
+    pub first_day: NaiveDate,
+    /// The day after the last day searched.
+    pub end_day: NaiveDate,
+    /// The part of each day in which slots are searched.
+    pub working_hours: (NaiveTime, NaiveTime),
+    /// Whether Saturdays and Sundays are searched too.
+    pub weekends: bool,
+    /// The minimum length of a slot.
+    pub duration: Duration,
+}
+
+impl SlotSearch {
+    /// Finds the free slots which are long enough, outside of the given busy intervals.
+    ///
+    /// ## Arguments
+    ///
+    /// * `tz` - The timezone in which the days and working hours are interpreted.
+    /// * `busy` - The busy intervals of every participant, in any order.
+    /// * `not_before` - The earliest time a slot may start, usually now.
+    ///
+    /// ## Returns
+    ///
+    /// * `Vec<Interval>` - The free slots in order, each at least `duration` long.
+    pub fn find_free_slots(
+        &self,
+        tz: Tz,
+        busy: &[Interval],
+        not_before: DateTime<Utc>,
+    ) -> Vec<Interval> {
+        let busy = merge_intervals(busy.to_vec());
+        let to_utc = |day: NaiveDate, time: NaiveTime| {
+            tz.from_local_datetime(&day.and_time(time))
+                .earliest()
+                .map(|date_time| date_time.to_utc())
+        };
+        let mut slots = vec![];
+        for day in self
+            .first_day
+            .iter_days()
+            .take_while(|day| *day < self.end_day)
+        {
+            if !self.weekends && matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+                continue;
+            }
+            let (Some(window_start), Some(window_end)) = (
+                to_utc(day, self.working_hours.0),
+                to_utc(day, self.working_hours.1),
+            ) else {
+                continue;
+            };
+            let mut cursor = window_start.max(not_before);
+            for (busy_start, busy_end) in &busy {
+                if *busy_end <= cursor || *busy_start >= window_end {
+                    continue;
+                }
+                if *busy_start > cursor {
+                    slots.push((cursor, *busy_start));
+                }
+                cursor = cursor.max(*busy_end);
+            }
+            if cursor < window_end {
+                slots.push((cursor, window_end));
+            }
+        }
+        slots.retain(|(start, end)| *end - *start >= self.duration);
+        slots
+    }
+}
+
+/// Sorts intervals and merges the ones which overlap or touch.
+pub fn merge_intervals(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort();
+    let mut merged: Vec<Interval> = vec![];
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 7, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_merge_intervals() {
+        let intervals = vec![
+            (at(24, 10, 0), at(24, 11, 0)),
+            (at(24, 8, 0), at(24, 9, 0)),
+            (at(24, 10, 30), at(24, 12, 0)),
+            (at(24, 12, 0), at(24, 12, 30)),
+        ];
+
+        assert_eq!(
+            merge_intervals(intervals),
+            vec![
+                (at(24, 8, 0), at(24, 9, 0)),
+                (at(24, 10, 0), at(24, 12, 30))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_free_slots() {
+        let tz: Tz = "UTC".parse().unwrap();
+        let search = SlotSearch {
+            // Wednesday to Monday
+            first_day: NaiveDate::from_ymd_opt(2024, 7, 24).unwrap(),
+            end_day: NaiveDate::from_ymd_opt(2024, 7, 30).unwrap(),
+            working_hours: (
+                NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            ),
+            weekends: false,
+            duration: Duration::minutes(45),
+        };
+        let busy = vec![
+            (at(24, 8, 0), at(24, 10, 0)),
+            (at(24, 10, 30), at(24, 12, 0)),
+            (at(24, 11, 0), at(24, 16, 30)),
+            (at(25, 0, 0), at(26, 0, 0)),
+            (at(26, 9, 0), at(26, 17, 0)),
+        ];
+
+        let slots = search.find_free_slots(tz, &busy, at(24, 0, 0));
+
+        assert_eq!(slots, vec![(at(29, 9, 0), at(29, 17, 0))]);
+
+        let search = SlotSearch {
+            duration: Duration::minutes(30),
+            weekends: true,
+            end_day: NaiveDate::from_ymd_opt(2024, 7, 28).unwrap(),
+            ..search
+        };
+        let slots = search.find_free_slots(tz, &busy, at(24, 10, 15));
+
+        assert_eq!(
+            slots,
+            vec![
+                (at(24, 16, 30), at(24, 17, 0)),
+                (at(27, 9, 0), at(27, 17, 0))
+            ]
+        );
+    }
+}
@@ -0,0 +1,966 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use google_calendar3::api::{Event, EventAttendee, EventDateTime, EventOrganizer};
+
+use super::error::GcalError;
+use super::event::get_conference_link;
+use super::recurrence::weekday_to_rrule;
+
+/// The product identifier written in exported calendars.
+const PRODUCT_ID: &str = "-//gcal//Google Calendar CLI//EN";
+/// The maximum length of a line in octets, longer lines are folded (RFC 5545, 3.1).
+const MAX_LINE_OCTETS: usize = 75;
+/// The formats of local date-times and dates in iCalendar files.
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+const DATE_FORMAT: &str = "%Y%m%d";
+/// The properties of an event holding recurrence rules and dates.
+const RECURRENCE_PROPERTIES: [&str; 4] = ["RRULE", "EXRULE", "RDATE", "EXDATE"];
+
+/// Converts events to an iCalendar file as described in RFC 5545.
+///
+/// Timed events are written in the time zone of their start, or `tz` if they have none, and
+/// a `VTIMEZONE` block is added for every time zone used. Recurring events keep their rules,
+/// and modified instances are written with a `RECURRENCE-ID`.
+///
+/// ## Arguments
+///
+/// * `events` - The events to convert, e.g. from `list_event_series`.
+/// * `tz` - The time zone of timed events without one.
+/// * `stamp` - The time the file is created at, written as `DTSTAMP`.
+///
+/// ## Returns
+///
+/// * `String` - The content of the file, with CRLF line endings.
+pub fn write_calendar(events: &[Event], tz: Tz, stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    // The years in which each time zone is used, by name.
+    let mut years: BTreeMap<&str, (Tz, i32, i32)> = BTreeMap::new();
+    for event in events {
+        let times = [&event.start, &event.end, &event.original_start_time];
+        for time in times.into_iter().flatten() {
+            if let Some(date_time) = time.date_time {
+                let time_zone = get_time_zone(time, tz);
+                let year = date_time.with_timezone(&time_zone).year();
+                let range = years
+                    .entry(time_zone.name())
+                    .or_insert((time_zone, year, year));
+                *range = (time_zone, range.1.min(year), range.2.max(year));
+            }
+        }
+    }
+    for (time_zone, first_year, last_year) in years.into_values() {
+        lines.extend(get_time_zone_lines(time_zone, first_year, last_year));
+    }
+    // Cancelled instances of recurring events have no times of their own, they are written
+    // as exceptions of their series instead.
+    let (cancelled, events): (Vec<&Event>, Vec<&Event>) = events.iter().partition(|event| {
+        event.status.as_deref() == Some("cancelled") && event.original_start_time.is_some()
+    });
+    for event in events {
+        let exceptions: Vec<String> = cancelled
+            .iter()
+            .filter(|instance| instance.recurring_event_id.is_some())
+            .filter(|instance| instance.recurring_event_id == event.id)
+            .filter_map(|instance| {
+                format_date_time("EXDATE", instance.original_start_time.as_ref()?, tz)
+            })
+            .collect();
+        lines.extend(get_event_lines(event, &exceptions, tz, stamp));
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// Reads the events of an iCalendar file.
+///
+/// Times are read in the time zone named by their `TZID`, which must be an IANA name such as
+/// `Europe/Budapest`; floating times and unknown time zones are read in `tz`.
+///
+/// ## Arguments
+///
+/// * `input` - The content of the file.
+/// * `tz` - The time zone of times without a known time zone.
+///
+/// ## Returns
+///
+/// * `Result<Vec<Event>>` - The events, or an error if the file is not a valid calendar.
+pub fn parse_calendar(input: &str, tz: Tz) -> Result<Vec<Event>> {
+    let mut events = vec![];
+    let mut components: Vec<String> = vec![];
+    let mut event = Event::default();
+    let mut duration: Option<Duration> = None;
+    let lines = unfold_lines(input.trim_start_matches('\u{feff}'));
+    if !lines
+        .first()
+        .is_some_and(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        bail!(GcalError::Parse(
+            "Not an iCalendar file, expected BEGIN:VCALENDAR".to_string()
+        ));
+    }
+    for line in lines {
+        let property = parse_property(&line)?;
+        match property.name.as_str() {
+            "BEGIN" => {
+                if property.value.eq_ignore_ascii_case("VEVENT") {
+                    event = Event::default();
+                    duration = None;
+                }
+                components.push(property.value.to_uppercase());
+                continue;
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VEVENT") {
+                    events.push(finish_event(event, duration)?);
+                    event = Event::default();
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if components.last().map(String::as_str) != Some("VEVENT") {
+            continue;
+        }
+        match property.name.as_str() {
+            "UID" => event.i_cal_uid = Some(property.value),
+            "SUMMARY" => event.summary = Some(unescape_text(&property.value)),
+            "DESCRIPTION" => event.description = Some(unescape_text(&property.value)),
+            "LOCATION" => event.location = Some(unescape_text(&property.value)),
+            "DTSTART" => event.start = Some(parse_event_date_time(&property, tz)?),
+            "DTEND" => event.end = Some(parse_event_date_time(&property, tz)?),
+            "RECURRENCE-ID" => {
+                event.original_start_time = Some(parse_event_date_time(&property, tz)?)
+            }
+            "DURATION" => duration = Some(parse_ics_duration(&property.value)?),
+            "STATUS" => event.status = Some(property.value.to_lowercase()),
+            "TRANSP" => event.transparency = Some(property.value.to_lowercase()),
+            "SEQUENCE" => event.sequence = property.value.parse().ok(),
+            "X-GOOGLE-CONFERENCE" => event.hangout_link = Some(property.value),
+            "ORGANIZER" => {
+                event.organizer = Some(EventOrganizer {
+                    email: Some(get_email(&property.value)),
+                    display_name: property.get_param("CN").map(str::to_string),
+                    ..Default::default()
+                })
+            }
+            "ATTENDEE" => event
+                .attendees
+                .get_or_insert_with(Vec::new)
+                .push(EventAttendee {
+                    email: Some(get_email(&property.value)),
+                    display_name: property.get_param("CN").map(str::to_string),
+                    optional: Some(true)
+                        .filter(|_| property.get_param("ROLE") == Some("OPT-PARTICIPANT")),
+                    response_status: property
+                        .get_param("PARTSTAT")
+                        .and_then(get_response_status)
+                        .map(str::to_string),
+                    ..Default::default()
+                }),
+            name if RECURRENCE_PROPERTIES.contains(&name) => {
+                event.recurrence.get_or_insert_with(Vec::new).push(line)
+            }
+            _ => {}
+        }
+    }
+    Ok(events)
+}
+
+/// Returns the lines of a `VEVENT` block, unfolded, with additional `EXDATE` lines.
+fn get_event_lines(
+    event: &Event,
+    exceptions: &[String],
+    tz: Tz,
+    stamp: DateTime<Utc>,
+) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VEVENT".to_string()];
+    let uid = event
+        .i_cal_uid
+        .clone()
+        .unwrap_or_else(|| format!("{}@google.com", event.id.as_deref().unwrap_or_default()));
+    lines.push(format!("UID:{}", uid));
+    lines.push(format!("DTSTAMP:{}Z", stamp.format(DATE_TIME_FORMAT)));
+    let times = [
+        ("RECURRENCE-ID", &event.original_start_time),
+        ("DTSTART", &event.start),
+        ("DTEND", &event.end),
+    ];
+    for (name, time) in times {
+        if let Some(line) = time
+            .as_ref()
+            .and_then(|time| format_date_time(name, time, tz))
+        {
+            lines.push(line);
+        }
+    }
+    lines.extend(event.recurrence.iter().flatten().cloned());
+    lines.extend(exceptions.iter().cloned());
+    let texts = [
+        ("SUMMARY", &event.summary),
+        ("DESCRIPTION", &event.description),
+        ("LOCATION", &event.location),
+    ];
+    for (name, text) in texts {
+        if let Some(text) = text {
+            lines.push(format!("{}:{}", name, escape_text(text)));
+        }
+    }
+    if let Some(status) = &event.status {
+        lines.push(format!("STATUS:{}", status.to_uppercase()));
+    }
+    if let Some(transparency) = &event.transparency {
+        lines.push(format!("TRANSP:{}", transparency.to_uppercase()));
+    }
+    if let Some(sequence) = event.sequence {
+        lines.push(format!("SEQUENCE:{}", sequence));
+    }
+    if let Some(organizer) = &event.organizer {
+        lines.push(format!(
+            "ORGANIZER{}:mailto:{}",
+            format_name_param(&organizer.display_name),
+            organizer.email.as_deref().unwrap_or_default()
+        ));
+    }
+    for attendee in event.attendees.iter().flatten() {
+        let role = if attendee.optional == Some(true) {
+            "OPT-PARTICIPANT"
+        } else {
+            "REQ-PARTICIPANT"
+        };
+        let status = match attendee.response_status.as_deref() {
+            Some("accepted") => "ACCEPTED",
+            Some("declined") => "DECLINED",
+            Some("tentative") => "TENTATIVE",
+            _ => "NEEDS-ACTION",
+        };
+        lines.push(format!(
+            "ATTENDEE{};ROLE={};PARTSTAT={}:mailto:{}",
+            format_name_param(&attendee.display_name),
+            role,
+            status,
+            attendee.email.as_deref().unwrap_or_default()
+        ));
+    }
+    if let Some(link) = get_conference_link(event) {
+        lines.push(format!("X-GOOGLE-CONFERENCE:{}", link));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// Formats a date or date-time property, e.g. `DTSTART;TZID=Europe/Budapest:20240727T100000`.
+fn format_date_time(name: &str, time: &EventDateTime, tz: Tz) -> Option<String> {
+    if let Some(date_time) = time.date_time {
+        let time_zone = get_time_zone(time, tz);
+        return Some(format!(
+            "{};TZID={}:{}",
+            name,
+            time_zone.name(),
+            date_time.with_timezone(&time_zone).format(DATE_TIME_FORMAT)
+        ));
+    }
+    let date = time.date?;
+    Some(format!("{};VALUE=DATE:{}", name, date.format(DATE_FORMAT)))
+}
+
+fn get_time_zone(time: &EventDateTime, tz: Tz) -> Tz {
+    time.time_zone
+        .as_deref()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(tz)
+}
+
+/// Returns the `VTIMEZONE` block of a time zone, covering the given years.
+///
+/// The offset changes of every year but the last are written one by one, those of the last
+/// year are repeated yearly with an `RRULE` so that later occurrences of recurring events
+/// are placed correctly too.
+fn get_time_zone_lines(tz: Tz, first_year: i32, last_year: i32) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    let year_start = |year: i32| Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+    // Starting a year early gives an observance for the times before the first change.
+    let transitions = get_transitions(tz, year_start(first_year - 1), year_start(last_year + 1));
+    if transitions.is_empty() {
+        let offset = tz.offset_from_utc_datetime(&year_start(first_year).naive_utc());
+        lines.extend(get_observance_lines(
+            year_start(first_year).naive_utc(),
+            offset,
+            offset,
+            None,
+        ));
+    }
+    for (time, before, after) in transitions {
+        let local_time =
+            (time + Duration::seconds(before.fix().local_minus_utc().into())).naive_utc();
+        let rule = (local_time.year() == last_year).then(|| get_yearly_rule(local_time.date()));
+        lines.extend(get_observance_lines(local_time, before, after, rule));
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+fn get_observance_lines(
+    start: NaiveDateTime,
+    before: <Tz as TimeZone>::Offset,
+    after: <Tz as TimeZone>::Offset,
+    rule: Option<String>,
+) -> Vec<String> {
+    let kind = if after.dst_offset().is_zero() {
+        "STANDARD"
+    } else {
+        "DAYLIGHT"
+    };
+    let mut lines = vec![
+        format!("BEGIN:{}", kind),
+        format!("DTSTART:{}", start.format(DATE_TIME_FORMAT)),
+        format!(
+            "TZOFFSETFROM:{}",
+            format_utc_offset(before.fix().local_minus_utc())
+        ),
+        format!(
+            "TZOFFSETTO:{}",
+            format_utc_offset(after.fix().local_minus_utc())
+        ),
+    ];
+    lines.extend(rule);
+    if let Some(name) = after.abbreviation() {
+        lines.push(format!("TZNAME:{}", name));
+    }
+    lines.push(format!("END:{}", kind));
+    lines
+}
+
+/// Finds the instants at which the offset or name of a time zone changes.
+///
+/// ## Returns
+///
+/// * `Vec<(DateTime<Utc>, Offset, Offset)>` - Each change with the offsets before and after it.
+fn get_transitions(
+    tz: Tz,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<(
+    DateTime<Utc>,
+    <Tz as TimeZone>::Offset,
+    <Tz as TimeZone>::Offset,
+)> {
+    let offset_at = |time: DateTime<Utc>| tz.offset_from_utc_datetime(&time.naive_utc());
+    let mut transitions = vec![];
+    let mut day = from;
+    while day < to {
+        let next_day = day + Duration::days(1);
+        let (before, after) = (offset_at(day), offset_at(next_day));
+        if before != after {
+            // The change happens after `low` and at or before `high`.
+            let (mut low, mut high) = (day, next_day);
+            while high - low > Duration::seconds(1) {
+                let middle = low + (high - low) / 2;
+                if offset_at(middle) == before {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            transitions.push((high, before, after));
+        }
+        day = next_day;
+    }
+    transitions
+}
+
+/// Returns a yearly rule matching the weekday of a date within its month, e.g.
+/// `RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU` for the last Sunday of March.
+fn get_yearly_rule(date: NaiveDate) -> String {
+    let next_month = NaiveDate::from_ymd_opt(date.year(), date.month(), 1)
+        .and_then(|first| first.checked_add_months(chrono::Months::new(1)));
+    let is_last = next_month.is_some_and(|next_month| date + Duration::days(7) >= next_month);
+    let ordinal = if is_last {
+        -1
+    } else {
+        (date.day() as i32 - 1) / 7 + 1
+    };
+    format!(
+        "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={}{}",
+        date.month(),
+        ordinal,
+        weekday_to_rrule(&date.weekday())
+    )
+}
+
+fn format_utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if seconds == 0 {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds)
+    }
+}
+
+fn format_name_param(name: &Option<String>) -> String {
+    name.as_ref()
+        .map(|name| format!(";CN=\"{}\"", name.replace('"', "'")))
+        .unwrap_or_default()
+}
+
+/// Escapes the characters with a special meaning in text values (RFC 5545, 3.3.11).
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut output = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => output.push('\n'),
+            Some(c) => output.push(c),
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+/// Folds a line into chunks of at most 75 octets, each ending with CRLF.
+fn fold_line(line: &str) -> String {
+    let mut output = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_OCTETS {
+            output.push_str("\r\n ");
+            length = 1;
+        }
+        output.push(c);
+        length += c.len_utf8();
+    }
+    output.push_str("\r\n");
+    output
+}
+
+/// Joins folded lines back together and drops empty lines.
+fn unfold_lines(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in input.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A content line, e.g. `ATTENDEE;CN="Jane":mailto:jane@x.com`.
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn get_param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn parse_property(line: &str) -> Result<Property> {
+    let invalid = || {
+        anyhow!(GcalError::Parse(format!(
+            "Invalid iCalendar line '{}'",
+            line
+        )))
+    };
+    // The value starts at the first colon which is not inside a quoted parameter value.
+    let mut in_quotes = false;
+    let colon = line
+        .char_indices()
+        .find(|(_, c)| {
+            if *c == '"' {
+                in_quotes = !in_quotes;
+            }
+            *c == ':' && !in_quotes
+        })
+        .map(|(i, _)| i)
+        .ok_or_else(invalid)?;
+    let mut parts = split_unquoted(&line[..colon], ';').into_iter();
+    let name = parts
+        .next()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(invalid)?;
+    let params = parts
+        .map(|param| {
+            let (key, value) = param.split_once('=').ok_or_else(invalid)?;
+            Ok((key.to_uppercase(), value.trim_matches('"').to_string()))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Property {
+        name: name.to_uppercase(),
+        params,
+        value: line[colon + 1..].to_string(),
+    })
+}
+
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut in_quotes) = (0, false);
+    for (i, c) in text.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            parts.push(&text[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Reads a `DATE` or `DATE-TIME` value, in UTC, in the time zone of its `TZID` or floating.
+fn parse_event_date_time(property: &Property, tz: Tz) -> Result<EventDateTime> {
+    let value = property.value.as_str();
+    let invalid = || {
+        anyhow!(GcalError::Parse(format!(
+            "Invalid date '{}' in {}",
+            value, property.name
+        )))
+    };
+    if property.get_param("VALUE") == Some("DATE") || value.len() == 8 {
+        return Ok(EventDateTime {
+            date: Some(NaiveDate::parse_from_str(value, DATE_FORMAT).map_err(|_| invalid())?),
+            ..Default::default()
+        });
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let date_time = NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT)
+            .map_err(|_| invalid())?
+            .and_utc();
+        return Ok(EventDateTime {
+            date_time: Some(date_time),
+            ..Default::default()
+        });
+    }
+    let time_zone: Tz = property
+        .get_param("TZID")
+        .and_then(|name| name.trim_start_matches('/').parse().ok())
+        .unwrap_or(tz);
+    let local = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT).map_err(|_| invalid())?;
+    let date_time = time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .ok_or_else(invalid)?;
+    Ok(EventDateTime {
+        date_time: Some(date_time.to_utc()),
+        time_zone: Some(time_zone.name().to_string()),
+        ..Default::default()
+    })
+}
+
+/// Reads a duration such as `PT1H30M`, `P1D` or `P2W` (RFC 5545, 3.3.6).
+fn parse_ics_duration(value: &str) -> Result<Duration> {
+    let invalid = || anyhow!(GcalError::Parse(format!("Invalid duration '{}'", value)));
+    let too_long = || {
+        anyhow!(GcalError::Parse(format!(
+            "The duration '{}' is too long",
+            value
+        )))
+    };
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    let mut has_value = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if !in_time && number.is_empty() => in_time = true,
+            _ => {
+                if number.is_empty() {
+                    return Err(invalid());
+                }
+                let amount: i64 = number.parse().map_err(|_| too_long())?;
+                let part = match (c, in_time) {
+                    ('W', false) => Duration::try_weeks(amount),
+                    ('D', false) => Duration::try_days(amount),
+                    ('H', true) => Duration::try_hours(amount),
+                    ('M', true) => Duration::try_minutes(amount),
+                    ('S', true) => Duration::try_seconds(amount),
+                    _ => return Err(invalid()),
+                };
+                duration = part
+                    .and_then(|part| duration.checked_add(&part))
+                    .ok_or_else(too_long)?;
+                number.clear();
+                has_value = true;
+            }
+        }
+    }
+    if !number.is_empty() || !has_value {
+        return Err(invalid());
+    }
+    Ok(if negative { -duration } else { duration })
+}
+
+/// Checks an event read from a file and fills in its end when only a duration, or nothing,
+/// was given.
+fn finish_event(mut event: Event, duration: Option<Duration>) -> Result<Event> {
+    let start = event.start.clone().ok_or_else(|| {
+        anyhow!(GcalError::Parse(format!(
+            "The event '{}' has no DTSTART",
+            event.summary.as_deref().unwrap_or_default()
+        )))
+    })?;
+    let out_of_range = || {
+        anyhow!(GcalError::Parse(format!(
+            "The end of the event '{}' is out of range",
+            event.summary.as_deref().unwrap_or_default()
+        )))
+    };
+    if event.end.is_none() {
+        event.end = Some(match (start.date_time, start.date) {
+            (Some(date_time), _) => EventDateTime {
+                date_time: Some(
+                    date_time
+                        .checked_add_signed(duration.unwrap_or_else(Duration::zero))
+                        .ok_or_else(out_of_range)?,
+                ),
+                ..start
+            },
+            (None, Some(date)) => EventDateTime {
+                date: Some(
+                    date.checked_add_signed(duration.unwrap_or_else(|| Duration::days(1)))
+                        .ok_or_else(out_of_range)?,
+                ),
+                ..start
+            },
+            _ => start,
+        });
+    }
+    Ok(event)
+}
+
+fn get_email(value: &str) -> String {
+    let lowercase = value.to_lowercase();
+    match lowercase.strip_prefix("mailto:") {
+        Some(_) => value["mailto:".len()..].to_string(),
+        None => value.to_string(),
+    }
+}
+
+fn get_response_status(participation_status: &str) -> Option<&'static str> {
+    match participation_status {
+        "ACCEPTED" => Some("accepted"),
+        "DECLINED" => Some("declined"),
+        "TENTATIVE" => Some("tentative"),
+        "NEEDS-ACTION" => Some("needsAction"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budapest() -> Tz {
+        "Europe/Budapest".parse().unwrap()
+    }
+
+    fn local(tz: Tz, day: u32, hour: u32) -> EventDateTime {
+        EventDateTime {
+            date_time: Some(
+                tz.with_ymd_and_hms(2024, 7, day, hour, 0, 0)
+                    .unwrap()
+                    .to_utc(),
+            ),
+            time_zone: Some(tz.name().to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn date(day: u32) -> EventDateTime {
+        EventDateTime {
+            date: NaiveDate::from_ymd_opt(2024, 7, day),
+            ..Default::default()
+        }
+    }
+
+    fn attendee(email: &str, name: Option<&str>, optional: bool, status: &str) -> EventAttendee {
+        EventAttendee {
+            email: Some(email.to_string()),
+            display_name: name.map(str::to_string),
+            optional: Some(true).filter(|_| optional),
+            response_status: Some(status.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Events as returned by the API, covering the fields kept in iCalendar files.
+    fn fixture_events() -> Vec<Event> {
+        let tokyo: Tz = "Asia/Tokyo".parse().unwrap();
+        vec![
+            Event {
+                i_cal_uid: Some("standup@google.com".to_string()),
+                summary: Some("Standup; daily, with \"notes\"".to_string()),
+                description: Some("Agenda:\n- blockers\n- C:\\path".to_string()),
+                start: Some(local(budapest(), 22, 10)),
+                end: Some(local(budapest(), 22, 11)),
+                recurrence: Some(vec![
+                    "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20241231T225959Z".to_string(),
+                    "EXDATE;TZID=Europe/Budapest:20240729T100000".to_string(),
+                ]),
+                organizer: Some(EventOrganizer {
+                    email: Some("me@x.com".to_string()),
+                    display_name: Some("Me".to_string()),
+                    ..Default::default()
+                }),
+                attendees: Some(vec![
+                    attendee("me@x.com", Some("Me"), false, "accepted"),
+                    attendee("jane@acme.com", Some("Doe, Jane"), false, "tentative"),
+                    attendee("bob@y.com", None, true, "needsAction"),
+                ]),
+                hangout_link: Some("https://meet.google.com/abc-defg-hij".to_string()),
+                status: Some("confirmed".to_string()),
+                sequence: Some(2),
+                ..Default::default()
+            },
+            Event {
+                i_cal_uid: Some("standup@google.com".to_string()),
+                summary: Some("Standup (moved)".to_string()),
+                original_start_time: Some(local(budapest(), 24, 10)),
+                start: Some(local(budapest(), 24, 14)),
+                end: Some(local(budapest(), 24, 15)),
+                status: Some("confirmed".to_string()),
+                ..Default::default()
+            },
+            Event {
+                i_cal_uid: Some("offsite@google.com".to_string()),
+                summary: Some("Offsite".to_string()),
+                location: Some("Lake Balaton, Hungary".to_string()),
+                start: Some(date(29)),
+                end: Some(date(31)),
+                transparency: Some("transparent".to_string()),
+                ..Default::default()
+            },
+            Event {
+                i_cal_uid: Some("tokyo@google.com".to_string()),
+                summary: Some("Call with the Tokyo office — 東京".repeat(3)),
+                start: Some(local(tokyo, 25, 9)),
+                end: Some(local(tokyo, 25, 10)),
+                status: Some("tentative".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let events = fixture_events();
+        let stamp = Utc.with_ymd_and_hms(2024, 7, 20, 12, 0, 0).unwrap();
+
+        let output = write_calendar(&events, budapest(), stamp);
+
+        // Events do not implement `PartialEq`, their JSON representations are compared instead.
+        let to_json = |events: &[Event]| serde_json::to_value(events).unwrap();
+        assert_eq!(
+            to_json(&parse_calendar(&output, budapest()).unwrap()),
+            to_json(&events)
+        );
+    }
+
+    #[test]
+    fn test_write_calendar() {
+        let events = fixture_events();
+        let stamp = Utc.with_ymd_and_hms(2024, 7, 20, 12, 0, 0).unwrap();
+
+        let output = write_calendar(&events, budapest(), stamp);
+
+        assert!(output.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(output.ends_with("END:VCALENDAR\r\n"));
+        assert!(output
+            .split("\r\n")
+            .all(|line| line.len() <= MAX_LINE_OCTETS && !line.contains('\n')));
+        let unfolded = output.replace("\r\n ", "");
+        for line in [
+            "DTSTART;TZID=Europe/Budapest:20240722T100000",
+            "RECURRENCE-ID;TZID=Europe/Budapest:20240724T100000",
+            "DTSTART;VALUE=DATE:20240729",
+            "DTEND;VALUE=DATE:20240731",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20241231T225959Z",
+            "SUMMARY:Standup\\; daily\\, with \"notes\"",
+            "DESCRIPTION:Agenda:\\n- blockers\\n- C:\\\\path",
+            "ATTENDEE;CN=\"Doe, Jane\";ROLE=REQ-PARTICIPANT;PARTSTAT=TENTATIVE:mailto:jane@acme.com",
+            "ATTENDEE;ROLE=OPT-PARTICIPANT;PARTSTAT=NEEDS-ACTION:mailto:bob@y.com",
+            "X-GOOGLE-CONFERENCE:https://meet.google.com/abc-defg-hij",
+            "TRANSP:TRANSPARENT",
+            "DTSTAMP:20240720T120000Z",
+        ] {
+            assert!(unfolded.contains(&format!("\r\n{}\r\n", line)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_instances() {
+        let series = Event {
+            id: Some("standup".to_string()),
+            start: Some(local(budapest(), 22, 10)),
+            end: Some(local(budapest(), 22, 11)),
+            recurrence: Some(vec!["RRULE:FREQ=DAILY".to_string()]),
+            ..Default::default()
+        };
+        let cancelled = Event {
+            id: Some("standup_20240723T080000Z".to_string()),
+            recurring_event_id: Some("standup".to_string()),
+            original_start_time: Some(local(budapest(), 23, 10)),
+            status: Some("cancelled".to_string()),
+            ..Default::default()
+        };
+
+        let output = write_calendar(&[series, cancelled], budapest(), Utc::now());
+        let events = parse_calendar(&output, budapest()).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].i_cal_uid.as_deref(), Some("standup@google.com"));
+        assert_eq!(
+            events[0].recurrence,
+            Some(vec![
+                "RRULE:FREQ=DAILY".to_string(),
+                "EXDATE;TZID=Europe/Budapest:20240723T100000".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_time_zone_blocks() {
+        let output = write_calendar(&fixture_events(), budapest(), Utc::now());
+        let block = |tz: &str| {
+            let start = output.find(&format!("TZID:{}\r\n", tz)).unwrap();
+            let end = start + output[start..].find("END:VTIMEZONE").unwrap();
+            output[start..end].to_string()
+        };
+
+        let budapest = block("Europe/Budapest");
+        assert!(budapest.contains(
+            "BEGIN:STANDARD\r\nDTSTART:20231029T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\nTZNAME:CET\r\n"
+        ));
+        assert!(budapest.contains(
+            "BEGIN:DAYLIGHT\r\nDTSTART:20240331T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\nTZNAME:CEST\r\n"
+        ));
+        assert!(budapest.contains("RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU"));
+
+        assert_eq!(
+            block("Asia/Tokyo"),
+            "TZID:Asia/Tokyo\r\nBEGIN:STANDARD\r\nDTSTART:20240101T000000\r\nTZOFFSETFROM:+0900\r\nTZOFFSETTO:+0900\r\nTZNAME:JST\r\nEND:STANDARD\r\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_calendar() {
+        let input = "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            BEGIN:VEVENT\n\
+            UID:a@x.com\n\
+            SUMMARY:Lunch with a very long title which is folded over \n \
+            two lines\n\
+            DTSTART:20240727T100000Z\n\
+            DURATION:PT1H30M\n\
+            BEGIN:VALARM\n\
+            DESCRIPTION:Reminder\n\
+            END:VALARM\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:b@x.com\n\
+            DTSTART;TZID=Unknown/Zone:20240727T100000\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            DTSTART;VALUE=DATE:20240727\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n";
+
+        let events = parse_calendar(input, budapest()).unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0].summary.as_deref(),
+            Some("Lunch with a very long title which is folded over two lines")
+        );
+        assert_eq!(
+            events[0].end.as_ref().unwrap().date_time,
+            Some(Utc.with_ymd_and_hms(2024, 7, 27, 11, 30, 0).unwrap())
+        );
+        assert_eq!(events[0].description, None);
+        assert_eq!(
+            events[1].start.as_ref().unwrap().date_time,
+            Some(Utc.with_ymd_and_hms(2024, 7, 27, 8, 0, 0).unwrap())
+        );
+        assert_eq!(
+            events[2].end.as_ref().unwrap().date,
+            NaiveDate::from_ymd_opt(2024, 7, 28)
+        );
+
+        assert!(parse_calendar("BEGIN:VEVENT\nEND:VEVENT\n", budapest()).is_err());
+        assert!(parse_calendar(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:x\nEND:VEVENT\nEND:VCALENDAR\n",
+            budapest()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_ics_duration() {
+        assert_eq!(
+            parse_ics_duration("PT1H30M").unwrap(),
+            Duration::minutes(90)
+        );
+        assert_eq!(parse_ics_duration("P1DT12H").unwrap(), Duration::hours(36));
+        assert_eq!(parse_ics_duration("P2W").unwrap(), Duration::days(14));
+        assert_eq!(
+            parse_ics_duration("-PT15M").unwrap(),
+            Duration::minutes(-15)
+        );
+        for invalid in [
+            "1H",
+            "PT",
+            "P1H",
+            "PT1D",
+            "PT1",
+            "P9999999999999W",
+            "PT99999999999999999999S",
+            "P15000000000W15000000000D",
+        ] {
+            assert!(parse_ics_duration(invalid).is_err(), "{}", invalid);
+        }
+
+        let input = "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            DTSTART:20240727T100000Z\n\
+            DURATION:P20000000W\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n";
+        assert!(parse_calendar(input, budapest()).is_err());
+    }
+}
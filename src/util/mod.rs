@@ -1,3 +1,14 @@
-pub mod file;
+pub mod attendee;
 pub mod calendar;
-pub mod date;
\ No newline at end of file
+pub mod config;
+pub mod date;
+pub mod error;
+pub mod event;
+pub mod file;
+pub mod freebusy;
+pub mod ics;
+pub mod output;
+pub mod plan;
+pub mod profile;
+pub mod prompt;
+pub mod recurrence;
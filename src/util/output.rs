@@ -0,0 +1,372 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use google_calendar3::api::{Event, EventDateTime};
+use serde::Serialize;
+
+use super::error::GcalError;
+use super::event::get_conference_link;
+
+/// The output formats supported by the commands listing events.
+pub const OUTPUT_FORMATS: [&str; 5] = ["table", "json", "ndjson", "csv", "tsv"];
+
+/// A flat, machine-readable representation of an event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventRecord {
+    pub id: String,
+    pub calendar: String,
+    pub start: String,
+    pub end: String,
+    pub all_day: bool,
+    pub summary: String,
+    pub location: String,
+    pub meet_link: String,
+    pub status: String,
+}
+
+impl EventRecord {
+    /// Creates a record from an event of the given calendar.
+    ///
+    /// Timed events get RFC 3339 start and end times in `tz`, all-day events get plain dates.
+    pub fn new(calendar: &str, event: &Event, tz: Tz) -> EventRecord {
+        let format_time = |time: Option<&EventDateTime>| match time {
+            Some(EventDateTime {
+                date_time: Some(date_time),
+                ..
+            }) => date_time.with_timezone(&tz).to_rfc3339(),
+            Some(EventDateTime {
+                date: Some(date), ..
+            }) => date.to_string(),
+            _ => String::new(),
+        };
+        EventRecord {
+            id: event.id.clone().unwrap_or_default(),
+            calendar: calendar.to_string(),
+            start: format_time(event.start.as_ref()),
+            end: format_time(event.end.as_ref()),
+            all_day: event
+                .start
+                .as_ref()
+                .is_some_and(|start| start.date.is_some()),
+            summary: event.summary.clone().unwrap_or_default(),
+            location: event.location.clone().unwrap_or_default(),
+            meet_link: get_conference_link(event).unwrap_or_default(),
+            status: event.status.clone().unwrap_or_default(),
+        }
+    }
+
+    fn fields(&self) -> [String; 9] {
+        [
+            self.id.clone(),
+            self.calendar.clone(),
+            self.start.clone(),
+            self.end.clone(),
+            self.all_day.to_string(),
+            self.summary.clone(),
+            self.location.clone(),
+            self.meet_link.clone(),
+            self.status.clone(),
+        ]
+    }
+}
+
+const RECORD_HEADER: [&str; 9] = [
+    "id",
+    "calendar",
+    "start",
+    "end",
+    "all_day",
+    "summary",
+    "location",
+    "meet_link",
+    "status",
+];
+
+/// Formats event records in one of the machine-readable `OUTPUT_FORMATS`.
+///
+/// ## Arguments
+///
+/// * `records` - The records to format.
+/// * `format` - One of `json`, `ndjson`, `csv` or `tsv`.
+///
+/// ## Returns
+///
+/// * `Result<String>` - The formatted output, or an error if the format is unknown or serialization fails.
+pub fn format_records(records: &[EventRecord], format: &str) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        "json" => {
+            output = serde_json::to_string_pretty(records)?;
+            output.push('\n');
+        }
+        "ndjson" => {
+            for record in records {
+                writeln!(output, "{}", serde_json::to_string(record)?)?;
+            }
+        }
+        "csv" | "tsv" => {
+            let mut builder = csv::WriterBuilder::new();
+            if format == "tsv" {
+                // Fields are escaped instead of quoted, so every line is one record.
+                builder.delimiter(b'\t').quote_style(csv::QuoteStyle::Never);
+            }
+            let mut writer = builder.from_writer(vec![]);
+            writer.write_record(RECORD_HEADER)?;
+            for record in records {
+                let fields = record.fields();
+                if format == "tsv" {
+                    writer.write_record(fields.iter().map(|f| escape_tsv(f)))?;
+                } else {
+                    writer.write_record(&fields)?;
+                }
+            }
+            output = String::from_utf8(writer.into_inner()?)?;
+        }
+        _ => bail!(GcalError::Parse(format!(
+            "Unknown output format '{}'",
+            format
+        ))),
+    }
+    Ok(output)
+}
+
+/// The placeholders which can be used in event templates.
+pub const TEMPLATE_FIELDS: [&str; 10] = [
+    "title",
+    "start",
+    "end",
+    "date",
+    "countdown",
+    "remaining",
+    "link",
+    "location",
+    "id",
+    "calendar",
+];
+
+/// Fills a template such as `{start} {title} (in {countdown})` with the fields of an event.
+///
+/// Times are formatted as `HH:MM` in `tz`; `countdown` is the time left until the start of
+/// the event and `remaining` the time left until its end, e.g. `1h05m`. Missing fields are
+/// replaced by an empty string.
+///
+/// ## Arguments
+///
+/// * `template` - The template, with placeholders from `TEMPLATE_FIELDS` between braces.
+/// * `record` - The record of the event, see `EventRecord::new`.
+/// * `event` - The event the record was created from.
+/// * `tz` - The timezone in which the times are displayed.
+/// * `now` - The time the countdowns are computed from.
+///
+/// ## Returns
+///
+/// * `Result<String>` - The filled template, or an error if it has an unknown or unclosed placeholder.
+pub fn format_template(
+    template: &str,
+    record: &EventRecord,
+    event: &Event,
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let get_time = |time: Option<&EventDateTime>| time.and_then(|time| time.date_time);
+    let start = get_time(event.start.as_ref());
+    let end = get_time(event.end.as_ref());
+    let format_time = |time: Option<DateTime<Utc>>, format: &str| {
+        time.map(|time| time.with_timezone(&tz).format(format).to_string())
+            .unwrap_or_default()
+    };
+    let countdown = |time: Option<DateTime<Utc>>| {
+        time.map(|time| format_countdown(time - now))
+            .unwrap_or_default()
+    };
+
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            bail!(GcalError::Parse(format!(
+                "Unclosed placeholder in template '{}'",
+                template
+            )));
+        };
+        let field = &rest[open + 1..open + close];
+        let value = match field {
+            "title" => record.summary.clone(),
+            "start" => format_time(start, "%H:%M"),
+            "end" => format_time(end, "%H:%M"),
+            "date" => format_time(start, "%Y-%m-%d"),
+            "countdown" => countdown(start),
+            "remaining" => countdown(end),
+            "link" => record.meet_link.clone(),
+            "location" => record.location.clone(),
+            "id" => record.id.clone(),
+            "calendar" => record.calendar.clone(),
+            _ => bail!(GcalError::Parse(format!(
+                "Unknown placeholder '{{{}}}', expected one of {}",
+                field,
+                TEMPLATE_FIELDS
+                    .map(|field| format!("{{{}}}", field))
+                    .join(", ")
+            ))),
+        };
+        output.push_str(&value);
+        rest = &rest[open + close + 1..];
+    }
+    output.push_str(rest);
+    Ok(output.trim_end().to_string())
+}
+
+/// Formats a duration compactly, rounding up to the minute, e.g. `45m`, `1h05m` or `2d3h`.
+pub fn format_countdown(duration: Duration) -> String {
+    let minutes = (duration.num_seconds().max(0) + 59) / 60;
+    let (days, hours) = (minutes / (24 * 60), minutes / 60 % 24);
+    if days > 0 {
+        format!("{}d{}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h{:02}m", hours, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Replaces the characters which would break a TSV line with escape sequences.
+fn escape_tsv(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::*;
+
+    fn events() -> Vec<EventRecord> {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+        let timed = Event {
+            id: Some("abc".to_string()),
+            summary: Some("Retro, \"Q3\"".to_string()),
+            start: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, 8, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            end: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, 9, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            hangout_link: Some("https://meet.google.com/abc-defg-hij".to_string()),
+            status: Some("confirmed".to_string()),
+            ..Default::default()
+        };
+        let all_day = Event {
+            id: Some("def".to_string()),
+            summary: Some("Offsite".to_string()),
+            start: Some(EventDateTime {
+                date: NaiveDate::from_ymd_opt(2024, 7, 29),
+                ..Default::default()
+            }),
+            end: Some(EventDateTime {
+                date: NaiveDate::from_ymd_opt(2024, 7, 30),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        vec![
+            EventRecord::new("primary", &timed, tz),
+            EventRecord::new("team", &all_day, tz),
+        ]
+    }
+
+    #[test]
+    fn test_event_record() {
+        let records = events();
+
+        assert_eq!(records[0].start, "2024-07-27T10:00:00+02:00");
+        assert_eq!(records[0].end, "2024-07-27T11:00:00+02:00");
+        assert!(!records[0].all_day);
+        assert_eq!(records[0].meet_link, "https://meet.google.com/abc-defg-hij");
+        assert_eq!(records[1].start, "2024-07-29");
+        assert!(records[1].all_day);
+    }
+
+    #[test]
+    fn test_format_records_as_ndjson() {
+        let output = format_records(&events(), "ndjson").unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["id"], "abc");
+        assert_eq!(first["all_day"], false);
+    }
+
+    #[test]
+    fn test_format_records_as_csv() {
+        let output = format_records(&events(), "csv").unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "id,calendar,start,end,all_day,summary,location,meet_link,status"
+        );
+        assert_eq!(
+            lines[1],
+            "abc,primary,2024-07-27T10:00:00+02:00,2024-07-27T11:00:00+02:00,false,\"Retro, \"\"Q3\"\"\",,https://meet.google.com/abc-defg-hij,confirmed"
+        );
+        assert_eq!(lines[2], "def,team,2024-07-29,2024-07-30,true,Offsite,,,");
+    }
+
+    #[test]
+    fn test_format_records_as_tsv() {
+        let output = format_records(&events(), "tsv").unwrap();
+
+        assert!(output.lines().nth(1).unwrap().contains("\tRetro, \"Q3\"\t"));
+        assert!(format_records(&events(), "xml").is_err());
+    }
+
+    #[test]
+    fn test_format_template() {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 7, 27, 7, 15, 30).unwrap();
+        let event = Event {
+            summary: Some("Retro".to_string()),
+            start: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, 8, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            end: Some(EventDateTime {
+                date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, 9, 0, 0).unwrap()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let record = EventRecord::new("primary", &event, tz);
+        let format = |template: &str| format_template(template, &record, &event, tz, now);
+
+        assert_eq!(
+            format("{start} {title} (in {countdown}) {link}").unwrap(),
+            "10:00 Retro (in 45m)"
+        );
+        assert_eq!(
+            format("{date} until {end}, {remaining} left").unwrap(),
+            "2024-07-27 until 11:00, 1h45m left"
+        );
+        assert!(format("{title").is_err());
+        assert!(format("{organizer}").is_err());
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        assert_eq!(format_countdown(Duration::seconds(30)), "1m");
+        assert_eq!(format_countdown(Duration::minutes(45)), "45m");
+        assert_eq!(format_countdown(Duration::minutes(65)), "1h05m");
+        assert_eq!(format_countdown(Duration::hours(51)), "2d3h");
+        assert_eq!(format_countdown(Duration::minutes(-5)), "0m");
+    }
+}
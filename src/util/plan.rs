@@ -0,0 +1,446 @@
+use anyhow::{anyhow, bail, Result};
+use chrono_tz::Tz;
+use google_calendar3::api::{Event, EventAttendee, EventDateTime};
+use serde_norway::{Mapping, Value};
+
+use super::attendee::parse_emails;
+use super::config::EventDefaults;
+use super::date::{get_date_from_string, get_end_date, get_naive_date_from_string, parse_duration};
+use super::error::GcalError;
+use super::event::new_conference_data;
+
+/// The columns of an event plan, only `title` and `start` are required.
+pub const PLAN_COLUMNS: [&str; 8] = [
+    "title",
+    "start",
+    "end",
+    "duration",
+    "calendar",
+    "attendees",
+    "location",
+    "conference",
+];
+
+/// An event read from a plan, ready to be inserted.
+#[derive(Debug)]
+pub struct PlannedEvent {
+    /// Where the event was defined, e.g. `line 3`.
+    pub origin: String,
+    /// The calendar ID or name of the `calendar` column, if any.
+    pub calendar: Option<String>,
+    pub event: Event,
+}
+
+/// The fields of an entry of a plan, before validation.
+type Entry = (String, Vec<(String, String)>);
+
+/// Reads a plan of events from a CSV file with a header line, or from a YAML list of entries.
+///
+/// Every entry is validated before returning, and the errors of all entries are reported
+/// together with the line they occur on, so nothing is created from a plan with mistakes.
+/// Timed events start with a date and time; entries starting with a date only are all-day
+/// events, and their `end` is the last day of the event. Missing durations and conferences,
+/// as well as the reminders, are taken from `defaults`.
+///
+/// ## Arguments
+///
+/// * `input` - The content of the file.
+/// * `tz` - The timezone in which the dates are interpreted.
+/// * `defaults` - The settings of new events from the configuration.
+///
+/// ## Returns
+///
+/// * `Result<Vec<PlannedEvent>>` - The events in order, or an error listing every invalid entry.
+pub fn parse_plan(input: &str, tz: Tz, defaults: &EventDefaults) -> Result<Vec<PlannedEvent>> {
+    let entries = if is_yaml(input) {
+        read_yaml_entries(input)?
+    } else {
+        read_csv_entries(input)?
+    };
+    if entries.is_empty() {
+        bail!(GcalError::Parse("The plan has no events".to_string()));
+    }
+
+    let mut events = vec![];
+    let mut errors = vec![];
+    for (origin, fields) in entries {
+        match parse_entry(&fields, tz, defaults) {
+            Ok((calendar, event)) => events.push(PlannedEvent {
+                origin,
+                calendar,
+                event,
+            }),
+            Err(e) => errors.push(format!("{}: {}", origin, e)),
+        }
+    }
+    if !errors.is_empty() {
+        bail!(GcalError::Parse(format!(
+            "Invalid plan, no event was created:\n  {}",
+            errors.join("\n  ")
+        )));
+    }
+    Ok(events)
+}
+
+/// A plan is read as YAML when its first entry starts with `- `, and as CSV otherwise.
+fn is_yaml(input: &str) -> bool {
+    input
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && line != &"---")
+        .is_some_and(|line| line.starts_with("- ") || line == "-")
+}
+
+fn read_csv_entries(input: &str) -> Result<Vec<Entry>> {
+    let invalid = |e: csv::Error| anyhow!(GcalError::Parse(format!("Invalid CSV: {}", e)));
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(input.as_bytes());
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(invalid)?
+        .iter()
+        .map(str::to_lowercase)
+        .collect();
+    check_columns(header.iter().map(String::as_str))?;
+
+    let mut entries = vec![];
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        // The position of a record includes the comments before it, which are skipped here.
+        let offset = record
+            .position()
+            .map_or(0, |position| position.byte() as usize);
+        let skipped = input[offset.min(input.len())..]
+            .lines()
+            .take_while(|line| line.trim().is_empty() || line.starts_with('#'))
+            .count();
+        let line = input[..offset.min(input.len())].lines().count() + skipped + 1;
+        let fields = header
+            .iter()
+            .cloned()
+            .zip(record.iter().map(str::to_string))
+            .collect();
+        entries.push((format!("line {}", line), fields));
+    }
+    Ok(entries)
+}
+
+fn read_yaml_entries(input: &str) -> Result<Vec<Entry>> {
+    let mappings: Vec<Mapping> = serde_norway::from_str(input)
+        .map_err(|e| anyhow!(GcalError::Parse(format!("Invalid YAML: {}", e))))?;
+    // Entries are matched with the lines starting a list item to report errors by line,
+    // unless the list is not written one item per line.
+    let item_lines: Vec<usize> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with('-') && !line.starts_with("---"))
+        .map(|(i, _)| i + 1)
+        .collect();
+    let by_line = item_lines.len() == mappings.len();
+
+    let mut entries = vec![];
+    for (i, mapping) in mappings.into_iter().enumerate() {
+        let origin = if by_line {
+            format!("line {}", item_lines[i])
+        } else {
+            format!("entry {}", i + 1)
+        };
+        let mut fields = vec![];
+        for (key, value) in mapping {
+            let key = yaml_to_string(key)
+                .ok_or_else(|| anyhow!(GcalError::Parse(format!("{}: invalid column", origin))))?
+                .to_lowercase();
+            let value = yaml_to_string(value).ok_or_else(|| {
+                anyhow!(GcalError::Parse(format!(
+                    "{}: invalid value for '{}'",
+                    origin, key
+                )))
+            })?;
+            fields.push((key, value));
+        }
+        check_columns(fields.iter().map(|(key, _)| key.as_str()))
+            .map_err(|e| anyhow!(GcalError::Parse(format!("{}: {}", origin, e))))?;
+        entries.push((origin, fields));
+    }
+    Ok(entries)
+}
+
+/// Converts a scalar, or a list of scalars joined with commas, to a string.
+fn yaml_to_string(value: Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::Bool(value) => Some(value.to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::String(value) => Some(value),
+        Value::Sequence(values) => values
+            .into_iter()
+            .map(yaml_to_string)
+            .collect::<Option<Vec<_>>>()
+            .map(|values| values.join(",")),
+        _ => None,
+    }
+}
+
+fn check_columns<'a>(columns: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    for column in columns {
+        if !PLAN_COLUMNS.contains(&column) {
+            bail!(GcalError::Parse(format!(
+                "Unknown column '{}', expected some of {}",
+                column,
+                PLAN_COLUMNS.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the event of an entry, returning its calendar along with it.
+fn parse_entry(
+    fields: &[(String, String)],
+    tz: Tz,
+    defaults: &EventDefaults,
+) -> Result<(Option<String>, Event)> {
+    let get = |column: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == column)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    };
+    let title = get("title").ok_or_else(|| anyhow!("Missing title"))?;
+    let start = get("start").ok_or_else(|| anyhow!("Missing start"))?;
+
+    let (start, end) = match get_date_from_string(tz, start) {
+        Ok(start_time) => {
+            let add = |duration| {
+                start_time
+                    .checked_add_signed(duration)
+                    .ok_or_else(|| anyhow!("The end of the event is out of range"))
+            };
+            let end_time = match (get("end"), get("duration")) {
+                (Some(_), Some(_)) => bail!("Only one of end and duration can be given"),
+                (Some(end), None) => get_end_date(tz, start_time, end)?,
+                (None, Some(duration)) => add(parse_duration(duration)?)?,
+                (None, None) => add(defaults.duration)?,
+            };
+            (
+                EventDateTime {
+                    date_time: Some(start_time),
+                    ..Default::default()
+                },
+                EventDateTime {
+                    date_time: Some(end_time),
+                    ..Default::default()
+                },
+            )
+        }
+        Err(e) => {
+            // A start without a time is the first day of an all-day event.
+            let Ok(first_day) = get_naive_date_from_string(start) else {
+                return Err(e);
+            };
+            if get("duration").is_some() {
+                bail!("All-day events take an end date instead of a duration");
+            }
+            let last_day = get("end")
+                .map(get_naive_date_from_string)
+                .transpose()?
+                .unwrap_or(first_day);
+            if last_day < first_day {
+                bail!("The end must not be before the start");
+            }
+            (
+                EventDateTime {
+                    date: Some(first_day),
+                    ..Default::default()
+                },
+                EventDateTime {
+                    date: Some(
+                        last_day
+                            .succ_opt()
+                            .ok_or_else(|| anyhow!("The end is out of range"))?,
+                    ),
+                    ..Default::default()
+                },
+            )
+        }
+    };
+
+    let attendees: Vec<EventAttendee> = parse_emails(get("attendees").map(str::to_string).iter())?
+        .into_iter()
+        .map(|email| EventAttendee {
+            email: Some(email),
+            ..Default::default()
+        })
+        .collect();
+    let conference = match get("conference").map(str::to_lowercase).as_deref() {
+        None => defaults.conference,
+        Some("false" | "no" | "0") => false,
+        Some("true" | "yes" | "1" | "x") => true,
+        Some(value) => bail!("Invalid conference '{}', expected yes or no", value),
+    };
+
+    let event = Event {
+        summary: Some(title.to_string()),
+        start: Some(start),
+        end: Some(end),
+        location: get("location").map(str::to_string),
+        attendees: Some(attendees).filter(|attendees| !attendees.is_empty()),
+        conference_data: conference.then(new_conference_data),
+        reminders: defaults.reminders.clone(),
+        ..Default::default()
+    };
+    Ok((get("calendar").map(str::to_string), event))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::*;
+    use crate::util::config::Config;
+
+    fn defaults() -> EventDefaults {
+        Config::default().event_defaults().unwrap()
+    }
+
+    fn budapest() -> Tz {
+        "Europe/Budapest".parse().unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> Option<chrono::DateTime<Utc>> {
+        budapest()
+            .with_ymd_and_hms(2024, 7, day, hour, minute, 0)
+            .single()
+            .map(|date_time| date_time.to_utc())
+    }
+
+    #[test]
+    fn test_parse_csv_plan() {
+        let input = "\
+title,start,end,duration,calendar,attendees,location,conference
+# Day one
+Welcome,2024-07-29 09:00,10:30,,,\"alice@x.com, bob@y.com\",Room 1,yes
+Setup,2024-07-29 11:00,,45m,Team,,,
+First week,2024-07-29,2024-08-02,,,,,
+";
+        let plan = parse_plan(input, budapest(), &defaults()).unwrap();
+
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan[0].origin, "line 3");
+        let welcome = &plan[0].event;
+        assert_eq!(welcome.summary.as_deref(), Some("Welcome"));
+        assert_eq!(welcome.start.as_ref().unwrap().date_time, at(29, 9, 0));
+        assert_eq!(welcome.end.as_ref().unwrap().date_time, at(29, 10, 30));
+        assert_eq!(welcome.attendees.as_ref().unwrap().len(), 2);
+        assert_eq!(welcome.location.as_deref(), Some("Room 1"));
+        assert!(welcome.conference_data.is_some());
+
+        assert_eq!(plan[1].calendar.as_deref(), Some("Team"));
+        assert_eq!(
+            plan[1].event.end.as_ref().unwrap().date_time,
+            at(29, 11, 45)
+        );
+        assert!(plan[1].event.conference_data.is_none());
+
+        let first_week = &plan[2].event;
+        assert_eq!(
+            first_week.start.as_ref().unwrap().date,
+            NaiveDate::from_ymd_opt(2024, 7, 29)
+        );
+        assert_eq!(
+            first_week.end.as_ref().unwrap().date,
+            NaiveDate::from_ymd_opt(2024, 8, 3)
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml_plan() {
+        let input = "\
+# Sprint 12
+- title: Planning
+  start: 2024-07-29 10:00
+  duration: 2h
+  attendees: [alice@x.com, bob@y.com]
+  conference: true
+
+- title: Review
+  start: 2024-08-09 15:00
+  end: 16:00
+";
+        let plan = parse_plan(input, budapest(), &defaults()).unwrap();
+
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[0].event.end.as_ref().unwrap().date_time, at(29, 12, 0));
+        assert_eq!(plan[0].event.attendees.as_ref().unwrap().len(), 2);
+        assert!(plan[0].event.conference_data.is_some());
+        assert_eq!(plan[1].origin, "line 8");
+    }
+
+    #[test]
+    fn test_plan_defaults() {
+        let mut config = Config::default();
+        config.set("duration", "30m").unwrap();
+        config.set("conference", "true").unwrap();
+        config.set("reminders", "5m").unwrap();
+        let input = "\
+title,start,conference
+Standup,2024-07-29 09:00,
+Focus,2024-07-29 10:00,no
+";
+        let plan = parse_plan(input, budapest(), &config.event_defaults().unwrap()).unwrap();
+
+        assert_eq!(plan[0].event.end.as_ref().unwrap().date_time, at(29, 9, 30));
+        assert!(plan[0].event.conference_data.is_some());
+        assert!(plan[1].event.conference_data.is_none());
+        assert!(plan[1].event.reminders.is_some());
+    }
+
+    #[test]
+    fn test_invalid_plans() {
+        let input = "\
+title,start,end,duration
+Ok,2024-07-29 09:00,,
+,2024-07-29 09:00,,
+Bad date,2024-13-45 09:00,,
+Both,2024-07-29 09:00,10:00,30m
+Backwards,2024-07-29 09:00,08:00,
+Forever,2024-07-29 09:00,,9999999999999m
+";
+        let message = parse_plan(input, budapest(), &defaults())
+            .unwrap_err()
+            .to_string();
+
+        assert!(message.contains("line 3: Missing title"), "{}", message);
+        assert!(message.contains("line 4: "), "{}", message);
+        assert!(
+            message.contains("line 5: Only one of end and duration"),
+            "{}",
+            message
+        );
+        assert!(message.contains("line 6: The end of the event must be after its start"));
+        assert!(
+            message.contains("line 7: The end of the event is out of range"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("line 2"));
+
+        assert!(parse_plan("title,begin\nx,y\n", budapest(), &defaults()).is_err());
+        assert!(parse_plan("title,start\n", budapest(), &defaults()).is_err());
+        assert!(parse_plan(
+            "- title: x\n  start: 2024-07-29 09:00\n  room: 1\n",
+            budapest(),
+            &defaults()
+        )
+        .is_err());
+        assert!(parse_plan(
+            "- title: x\n  start: 2024-07-29 09:00\n  conference: maybe\n",
+            budapest(),
+            &defaults()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::error::GcalError;
+use super::file;
+
+/// The directory holding the secret and token store used without a profile, relative to the
+/// home directory. Profiles are stored in its `profiles` subdirectory.
+pub const GCAL_DIR: &str = ".gcal";
+
+/// Returns the absolute path of the gcal directory.
+pub fn get_gcal_dir() -> Result<PathBuf> {
+    file::get_absolute_path(GCAL_DIR).map_err(|e| anyhow!(GcalError::Config(e.to_string())))
+}
+
+/// Checks that a profile name can be used as a directory name.
+pub fn check_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(GcalError::Parse(format!(
+            "Invalid profile name '{}', use letters, digits, - and _ only",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the directory holding the secret and token store of a profile.
+///
+/// ## Arguments
+///
+/// * `gcal_dir` - The gcal directory, see `get_gcal_dir`.
+/// * `profile` - The profile name, or `None` for the files directly in the gcal directory.
+///
+/// ## Returns
+///
+/// * `Result<PathBuf>` - The directory, or an error if the name is invalid.
+pub fn get_profile_dir(gcal_dir: &Path, profile: Option<&str>) -> Result<PathBuf> {
+    match profile {
+        Some(name) => {
+            check_profile_name(name)?;
+            Ok(gcal_dir.join("profiles").join(name))
+        }
+        None => Ok(gcal_dir.to_path_buf()),
+    }
+}
+
+/// Returns the directory of an existing profile, or a not found error.
+pub fn find_profile_dir(gcal_dir: &Path, profile: &str) -> Result<PathBuf> {
+    let dir = get_profile_dir(gcal_dir, Some(profile))?;
+    if !dir.is_dir() {
+        bail!(GcalError::NotFound(format!(
+            "Profile '{}' not found, create it with gcal profile add {}",
+            profile, profile
+        )));
+    }
+    Ok(dir)
+}
+
+/// Returns the names of the profiles, sorted alphabetically.
+pub fn list_profiles(gcal_dir: &Path) -> Result<Vec<String>> {
+    let entries = match fs::read_dir(gcal_dir.join("profiles")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => bail!(GcalError::Config(format!(
+            "Failed to read the profiles: {}",
+            e
+        ))),
+    };
+    let mut profiles: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| check_profile_name(name).is_ok())
+        .collect();
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// Creates the directory of a new profile, copying the given Google application secret into it.
+///
+/// ## Arguments
+///
+/// * `gcal_dir` - The gcal directory, see `get_gcal_dir`.
+/// * `profile` - The name of the new profile.
+/// * `secret` - An optional `secret.json` downloaded from the Google Cloud Console.
+///
+/// ## Returns
+///
+/// * `Result<PathBuf>` - The directory of the profile, or an error if it already exists or
+///   cannot be created.
+pub fn create_profile(gcal_dir: &Path, profile: &str, secret: Option<&Path>) -> Result<PathBuf> {
+    let dir = get_profile_dir(gcal_dir, Some(profile))?;
+    if dir.exists() {
+        bail!(GcalError::Parse(format!(
+            "Profile '{}' already exists",
+            profile
+        )));
+    }
+    let config_error = |e: std::io::Error| {
+        anyhow!(GcalError::Config(format!(
+            "Failed to create profile '{}': {}",
+            profile, e
+        )))
+    };
+    fs::create_dir_all(&dir).map_err(config_error)?;
+    if let Some(secret) = secret {
+        if let Err(e) = fs::copy(secret, dir.join("secret.json")) {
+            let _ = fs::remove_dir_all(&dir);
+            bail!(GcalError::Config(format!(
+                "Failed to copy the secret {}: {}",
+                secret.display(),
+                e
+            )));
+        }
+    }
+    Ok(dir)
+}
+
+/// Deletes a profile with its secret and token store.
+pub fn remove_profile(gcal_dir: &Path, profile: &str) -> Result<()> {
+    let dir = find_profile_dir(gcal_dir, profile)?;
+    fs::remove_dir_all(&dir).map_err(|e| {
+        anyhow!(GcalError::Config(format!(
+            "Failed to remove profile '{}': {}",
+            profile, e
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_get_profile_dir() {
+        let gcal_dir = Path::new("/home/user/.gcal");
+
+        assert_eq!(get_profile_dir(gcal_dir, None).unwrap(), gcal_dir);
+        assert_eq!(
+            get_profile_dir(gcal_dir, Some("work")).unwrap(),
+            Path::new("/home/user/.gcal/profiles/work")
+        );
+        assert!(get_profile_dir(gcal_dir, Some("../work")).is_err());
+        assert!(get_profile_dir(gcal_dir, Some("")).is_err());
+    }
+
+    #[test]
+    fn test_create_list_and_remove_profiles() {
+        let gcal_dir = env::temp_dir().join("test_gcal_profiles");
+        let _ = fs::remove_dir_all(&gcal_dir);
+        assert!(list_profiles(&gcal_dir).unwrap().is_empty());
+
+        let secret = gcal_dir.join("secret.json");
+        fs::create_dir_all(&gcal_dir).unwrap();
+        fs::write(&secret, "{}").unwrap();
+        let work = create_profile(&gcal_dir, "work", Some(&secret)).unwrap();
+        create_profile(&gcal_dir, "personal", None).unwrap();
+
+        assert!(work.join("secret.json").exists());
+        assert!(create_profile(&gcal_dir, "work", None).is_err());
+        assert_eq!(list_profiles(&gcal_dir).unwrap(), vec!["personal", "work"]);
+
+        remove_profile(&gcal_dir, "work").unwrap();
+        assert_eq!(list_profiles(&gcal_dir).unwrap(), vec!["personal"]);
+        assert!(remove_profile(&gcal_dir, "work").is_err());
+        assert!(find_profile_dir(&gcal_dir, "personal").is_ok());
+
+        let _ = fs::remove_dir_all(&gcal_dir);
+    }
+}
@@ -0,0 +1,47 @@
+use std::io::{self, BufRead, Write};
+
+use anyhow::Result;
+
+/// Asks the user a yes/no question on the terminal.
+///
+/// ## Arguments
+///
+/// * `question` - The question to print, without the `[y/N]` suffix.
+///
+/// ## Returns
+///
+/// * `Result<bool>` - `true` if the user answered `y` or `yes`, `false` otherwise.
+pub fn confirm(question: &str) -> Result<bool> {
+    let answer = read_line(&format!("{} [y/N] ", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Prints a numbered list of options and lets the user pick one of them.
+///
+/// ## Arguments
+///
+/// * `question` - The prompt printed after the options.
+/// * `options` - The labels of the options, in the order they should be listed.
+///
+/// ## Returns
+///
+/// * `Result<Option<usize>>` - The index of the chosen option, or `None` if the user entered nothing or an invalid number.
+pub fn pick(question: &str, options: &[String]) -> Result<Option<usize>> {
+    for (i, option) in options.iter().enumerate() {
+        println!("{:>3}) {}", i + 1, option);
+    }
+    let answer = read_line(question)?;
+    Ok(answer
+        .parse::<usize>()
+        .ok()
+        .filter(|&n| n >= 1 && n <= options.len())
+        .map(|n| n - 1))
+}
+
+fn read_line(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
@@ -0,0 +1,495 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use google_calendar3::api::EventDateTime;
+
+use super::error::GcalError;
+
+/// The recurrence frequencies supported when creating events.
+pub const FREQUENCIES: [&str; 4] = ["daily", "weekly", "monthly", "yearly"];
+
+/// Upper bound for the number of periods scanned when computing occurrences, so that rules
+/// which rarely match (e.g. every February 29th) cannot loop forever.
+const MAX_PERIODS: u32 = 10_000;
+
+/// How often a recurring event repeats, as in the `FREQ` part of an RFC 5545 `RRULE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl FromStr for Frequency {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Frequency> {
+        match value.to_lowercase().as_str() {
+            "daily" => Ok(Frequency::Daily),
+            "weekly" => Ok(Frequency::Weekly),
+            "monthly" => Ok(Frequency::Monthly),
+            "yearly" => Ok(Frequency::Yearly),
+            _ => Err(anyhow!(GcalError::Parse(format!(
+                "Invalid frequency '{}', expected one of {}",
+                value,
+                FREQUENCIES.join(", ")
+            )))),
+        }
+    }
+}
+
+impl Frequency {
+    fn as_rrule(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// A simple recurrence rule, covering the subset of RFC 5545 that can be given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub weekdays: Vec<Weekday>,
+    pub until: Option<NaiveDate>,
+    pub count: Option<u32>,
+}
+
+impl Recurrence {
+    /// Creates a recurrence rule and validates it against the first occurrence.
+    ///
+    /// ## Errors
+    ///
+    /// This function will return an error if:
+    /// - The interval or the count is zero.
+    /// - Both `until` and `count` are given.
+    /// - Weekdays are given for a non-weekly frequency.
+    /// - The `until` date is before the first occurrence.
+    pub fn new(
+        frequency: Frequency,
+        interval: u32,
+        weekdays: Vec<Weekday>,
+        until: Option<NaiveDate>,
+        count: Option<u32>,
+        start: NaiveDate,
+    ) -> Result<Recurrence> {
+        if interval == 0 {
+            bail!(GcalError::Parse(
+                "The recurrence interval must be at least 1".to_string()
+            ));
+        }
+        if count == Some(0) {
+            bail!(GcalError::Parse(
+                "The recurrence count must be at least 1".to_string()
+            ));
+        }
+        if until.is_some() && count.is_some() {
+            bail!(GcalError::Parse(
+                "A recurrence can end either at a date or after a count, not both".to_string()
+            ));
+        }
+        if !weekdays.is_empty() && frequency != Frequency::Weekly {
+            bail!(GcalError::Parse(
+                "Weekdays can only be given for weekly recurrences".to_string()
+            ));
+        }
+        if until.is_some_and(|until| until < start) {
+            bail!(GcalError::Parse(
+                "The recurrence must not end before the first occurrence".to_string()
+            ));
+        }
+        Ok(Recurrence {
+            frequency,
+            interval,
+            weekdays,
+            until,
+            count,
+        })
+    }
+
+    /// Formats the rule as an RFC 5545 `RRULE` line.
+    ///
+    /// For timed events, `UNTIL` is the end of the given day in `tz`, converted to UTC as
+    /// required by the RFC. For all-day events, it is the plain date.
+    pub fn to_rrule(&self, tz: Tz, all_day: bool) -> String {
+        let mut parts = vec![format!("FREQ={}", self.frequency.as_rrule())];
+        if self.interval > 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if !self.weekdays.is_empty() {
+            let weekdays: Vec<&str> = self.weekdays.iter().map(weekday_to_rrule).collect();
+            parts.push(format!("BYDAY={}", weekdays.join(",")));
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={}", count));
+        }
+        if let Some(until) = self.until {
+            if all_day {
+                parts.push(format!("UNTIL={}", until.format("%Y%m%d")));
+            } else {
+                let end_of_day = until.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+                let end_of_day = tz
+                    .from_local_datetime(&end_of_day)
+                    .latest()
+                    .map_or(end_of_day, |date_time| date_time.naive_utc());
+                parts.push(format!("UNTIL={}", end_of_day.format("%Y%m%dT%H%M%SZ")));
+            }
+        }
+        format!("RRULE:{}", parts.join(";"))
+    }
+
+    /// Computes the first occurrences of the rule, starting at `start`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `start` - The local start of the first occurrence.
+    /// * `limit` - The maximum number of occurrences to return.
+    ///
+    /// ## Returns
+    ///
+    /// * `Vec<NaiveDateTime>` - The local start of each occurrence, in order.
+    pub fn get_occurrences(&self, start: NaiveDateTime, limit: usize) -> Vec<NaiveDateTime> {
+        let limit = self.count.map_or(limit, |count| limit.min(count as usize));
+        let mut occurrences = vec![];
+        for period in 0..MAX_PERIODS {
+            let Some(dates) = self.get_period_dates(start.date(), period) else {
+                break;
+            };
+            for date in dates {
+                if date < start.date() {
+                    continue;
+                }
+                if occurrences.len() >= limit || self.until.is_some_and(|until| date > until) {
+                    return occurrences;
+                }
+                occurrences.push(date.and_time(start.time()));
+            }
+        }
+        occurrences
+    }
+
+    /// Returns the candidate dates of the given period, e.g. the selected days of a week, or
+    /// `None` if the period is past the last representable date.
+    fn get_period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval)?;
+        let dates = match self.frequency {
+            Frequency::Daily => vec![start.checked_add_signed(Duration::try_days(step.into())?)?],
+            Frequency::Weekly if self.weekdays.is_empty() => {
+                vec![start.checked_add_signed(Duration::try_weeks(step.into())?)?]
+            }
+            Frequency::Weekly => {
+                let week_start = start
+                    .checked_sub_signed(Duration::days(
+                        start.weekday().num_days_from_monday() as i64
+                    ))?
+                    .checked_add_signed(Duration::try_weeks(step.into())?)?;
+                let mut dates = self
+                    .weekdays
+                    .iter()
+                    .map(|day| {
+                        week_start
+                            .checked_add_signed(Duration::days(day.num_days_from_monday() as i64))
+                    })
+                    .collect::<Option<Vec<NaiveDate>>>()?;
+                dates.sort();
+                dates
+            }
+            Frequency::Monthly => {
+                let month = start.month0().checked_add(step)?;
+                let year = start.year().checked_add(i32::try_from(month / 12).ok()?)?;
+                NaiveDate::from_ymd_opt(year, month % 12 + 1, start.day())
+                    .into_iter()
+                    .collect()
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+                NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+        Some(dates)
+    }
+}
+
+/// Parses a comma separated list of weekdays, e.g. `mon,wed` or `Monday,Friday`.
+pub fn parse_weekdays(value: &str) -> Result<Vec<Weekday>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|day| !day.is_empty())
+        .map(|day| {
+            day.parse::<Weekday>()
+                .map_err(|_| anyhow!(GcalError::Parse(format!("Invalid weekday '{}'", day))))
+        })
+        .collect()
+}
+
+/// Returns the two-letter `BYDAY` code of a weekday, e.g. `MO`.
+pub(crate) fn weekday_to_rrule(weekday: &Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Returns the `UNTIL` value that ends a recurrence right before the given occurrence.
+///
+/// Timed occurrences produce a UTC timestamp one second before their start, all-day
+/// occurrences produce the date of the previous day, as required by RFC 5545.
+///
+/// ## Arguments
+///
+/// * `start` - The start of the occurrence which should no longer be part of the series.
+///
+/// ## Returns
+///
+/// * `Option<String>` - The formatted `UNTIL` value, or `None` if the start has neither a date nor a date-time.
+pub fn get_until_before(start: &EventDateTime) -> Option<String> {
+    if let Some(date_time) = start.date_time {
+        return Some(
+            (date_time - Duration::seconds(1))
+                .format("%Y%m%dT%H%M%SZ")
+                .to_string(),
+        );
+    }
+    start
+        .date
+        .map(|date| (date - Duration::days(1)).format("%Y%m%d").to_string())
+}
+
+/// Rewrites every `RRULE` line so that the series ends at the given `UNTIL` value.
+///
+/// Existing `UNTIL` and `COUNT` parts are dropped, since RFC 5545 does not allow both of them
+/// in the same rule. Other lines such as `EXDATE` or `RDATE` are kept unchanged.
+///
+/// ## Arguments
+///
+/// * `recurrence` - The recurrence lines of the recurring event.
+/// * `until` - The new `UNTIL` value, see `get_until_before`.
+///
+/// ## Returns
+///
+/// * `Vec<String>` - The updated recurrence lines.
+pub fn truncate_recurrence(recurrence: &[String], until: &str) -> Vec<String> {
+    recurrence
+        .iter()
+        .map(|line| match line.strip_prefix("RRULE:") {
+            Some(rule) => {
+                let mut parts: Vec<&str> = rule
+                    .split(';')
+                    .filter(|part| !part.starts_with("UNTIL=") && !part.starts_with("COUNT="))
+                    .collect();
+                let until = format!("UNTIL={}", until);
+                parts.push(&until);
+                format!("RRULE:{}", parts.join(";"))
+            }
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn at(date: NaiveDate, hour: u32) -> NaiveDateTime {
+        date.and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_recurrence_to_rrule() {
+        let tz: Tz = "Europe/Budapest".parse().unwrap();
+        let start = date(2024, 7, 22);
+
+        let weekly = Recurrence::new(
+            Frequency::Weekly,
+            2,
+            vec![Weekday::Mon, Weekday::Wed],
+            Some(date(2024, 12, 31)),
+            None,
+            start,
+        )
+        .unwrap();
+        assert_eq!(
+            weekly.to_rrule(tz, false),
+            "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20241231T225959Z"
+        );
+        assert_eq!(
+            weekly.to_rrule(tz, true),
+            "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20241231"
+        );
+
+        let daily = Recurrence::new(Frequency::Daily, 1, vec![], None, Some(10), start).unwrap();
+        assert_eq!(daily.to_rrule(tz, false), "RRULE:FREQ=DAILY;COUNT=10");
+    }
+
+    #[test]
+    fn test_recurrence_validation() {
+        let start = date(2024, 7, 22);
+
+        assert!(Recurrence::new(Frequency::Daily, 0, vec![], None, None, start).is_err());
+        assert!(Recurrence::new(Frequency::Daily, 1, vec![], None, Some(0), start).is_err());
+        assert!(Recurrence::new(
+            Frequency::Daily,
+            1,
+            vec![],
+            Some(date(2024, 8, 1)),
+            Some(3),
+            start
+        )
+        .is_err());
+        assert!(
+            Recurrence::new(Frequency::Monthly, 1, vec![Weekday::Mon], None, None, start).is_err()
+        );
+        assert!(Recurrence::new(
+            Frequency::Daily,
+            1,
+            vec![],
+            Some(date(2024, 7, 1)),
+            None,
+            start
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_get_occurrences() {
+        let start = at(date(2024, 7, 24), 10); // Wednesday
+
+        let weekly = Recurrence::new(
+            Frequency::Weekly,
+            1,
+            vec![Weekday::Fri, Weekday::Mon],
+            None,
+            Some(3),
+            start.date(),
+        )
+        .unwrap();
+        assert_eq!(
+            weekly.get_occurrences(start, 5),
+            vec![
+                at(date(2024, 7, 26), 10),
+                at(date(2024, 7, 29), 10),
+                at(date(2024, 8, 2), 10)
+            ]
+        );
+
+        let daily = Recurrence::new(
+            Frequency::Daily,
+            2,
+            vec![],
+            Some(date(2024, 7, 29)),
+            None,
+            start.date(),
+        )
+        .unwrap();
+        assert_eq!(
+            daily.get_occurrences(start, 5),
+            vec![
+                at(date(2024, 7, 24), 10),
+                at(date(2024, 7, 26), 10),
+                at(date(2024, 7, 28), 10)
+            ]
+        );
+
+        let start = at(date(2024, 1, 31), 9);
+        let monthly =
+            Recurrence::new(Frequency::Monthly, 1, vec![], None, None, start.date()).unwrap();
+        assert_eq!(
+            monthly.get_occurrences(start, 3),
+            vec![
+                at(date(2024, 1, 31), 9),
+                at(date(2024, 3, 31), 9),
+                at(date(2024, 5, 31), 9)
+            ]
+        );
+
+        let start = at(date(2024, 2, 29), 9);
+        let yearly =
+            Recurrence::new(Frequency::Yearly, 1, vec![], None, None, start.date()).unwrap();
+        assert_eq!(
+            yearly.get_occurrences(start, 2),
+            vec![at(date(2024, 2, 29), 9), at(date(2028, 2, 29), 9)]
+        );
+
+        for frequency in [
+            Frequency::Daily,
+            Frequency::Weekly,
+            Frequency::Monthly,
+            Frequency::Yearly,
+        ] {
+            let huge =
+                Recurrence::new(frequency, u32::MAX, vec![], None, None, start.date()).unwrap();
+            assert_eq!(huge.get_occurrences(start, 3), vec![start]);
+        }
+    }
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(
+            parse_weekdays("mon, Wed,friday").unwrap(),
+            vec![Weekday::Mon, Weekday::Wed, Weekday::Fri]
+        );
+        assert!(parse_weekdays("mon,funday").is_err());
+    }
+
+    #[test]
+    fn test_get_until_before_timed_event() {
+        let start = EventDateTime {
+            date_time: Some(Utc.with_ymd_and_hms(2024, 7, 27, 9, 0, 0).unwrap()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_until_before(&start),
+            Some("20240727T085959Z".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_until_before_all_day_event() {
+        let start = EventDateTime {
+            date: NaiveDate::from_ymd_opt(2024, 8, 1),
+            ..Default::default()
+        };
+
+        assert_eq!(get_until_before(&start), Some("20240731".to_string()));
+    }
+
+    #[test]
+    fn test_truncate_recurrence() {
+        let recurrence = vec![
+            "RRULE:FREQ=WEEKLY;COUNT=10;BYDAY=MO,WE".to_string(),
+            "EXDATE;TZID=Europe/Budapest:20240729T100000".to_string(),
+        ];
+
+        let truncated = truncate_recurrence(&recurrence, "20240805T085959Z");
+
+        assert_eq!(
+            truncated,
+            vec![
+                "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20240805T085959Z".to_string(),
+                "EXDATE;TZID=Europe/Budapest:20240729T100000".to_string(),
+            ]
+        );
+    }
+}